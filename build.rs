@@ -1,12 +1,17 @@
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::configure().format(false).compile(
-        &[
-            "googleapis/google/cloud/bigquery/storage/v1/arrow.proto",
-            "googleapis/google/cloud/bigquery/storage/v1/avro.proto",
-            "googleapis/google/cloud/bigquery/storage/v1/storage.proto",
-            "googleapis/google/cloud/bigquery/storage/v1/stream.proto",
-        ],
-        &["googleapis"],
-    )?;
+    // The v1 protos use proto3 `optional` fields, which older versions of protoc only
+    // accept behind this flag.
+    tonic_build::configure()
+        .format(false)
+        .protoc_arg("--experimental_allow_proto3_optional")
+        .compile(
+            &[
+                "googleapis/google/cloud/bigquery/storage/v1/arrow.proto",
+                "googleapis/google/cloud/bigquery/storage/v1/avro.proto",
+                "googleapis/google/cloud/bigquery/storage/v1/storage.proto",
+                "googleapis/google/cloud/bigquery/storage/v1/stream.proto",
+            ],
+            &["googleapis"],
+        )?;
     Ok(())
 }
@@ -0,0 +1,107 @@
+//! Derivation of an Avro schema from the Arrow schema of a read session, following the
+//! types BigQuery uses when serializing rows as Avro.
+use arrow::datatypes::{DataType, Field, FieldRef, Schema, TimeUnit};
+use arrow::error::ArrowError;
+
+use crate::Error;
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn record(name: &str, fields: &[FieldRef]) -> Result<String, Error> {
+    let fields = fields
+        .iter()
+        .map(|field| avro_field(field))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(format!(
+        r#"{{"type":"record","name":{},"fields":[{}]}}"#,
+        json_string(name),
+        fields.join(",")
+    ))
+}
+
+fn avro_field(field: &Field) -> Result<String, Error> {
+    Ok(format!(
+        r#"{{"name":{},"type":{}}}"#,
+        json_string(field.name()),
+        avro_type(field)?
+    ))
+}
+
+fn avro_type(field: &Field) -> Result<String, Error> {
+    let avro_type = match field.data_type() {
+        DataType::Int64 => r#""long""#.to_string(),
+        DataType::Float64 => r#""double""#.to_string(),
+        DataType::Boolean => r#""boolean""#.to_string(),
+        DataType::Utf8 => r#""string""#.to_string(),
+        DataType::Binary => r#""bytes""#.to_string(),
+        DataType::Decimal128(precision, scale) => format!(
+            r#"{{"type":"bytes","logicalType":"decimal","precision":{},"scale":{}}}"#,
+            precision, scale
+        ),
+        DataType::Decimal256(precision, scale) => format!(
+            r#"{{"type":"bytes","logicalType":"decimal","precision":{},"scale":{}}}"#,
+            precision, scale
+        ),
+        DataType::Date32 => r#"{"type":"int","logicalType":"date"}"#.to_string(),
+        DataType::Time64(TimeUnit::Microsecond) => {
+            r#"{"type":"long","logicalType":"time-micros"}"#.to_string()
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, Some(_)) => {
+            r#"{"type":"long","logicalType":"timestamp-micros"}"#.to_string()
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            r#"{"type":"string","sqlType":"DATETIME"}"#.to_string()
+        }
+        DataType::List(item) => format!(r#"{{"type":"array","items":{}}}"#, avro_type(item)?),
+        DataType::Struct(fields) => record(field.name(), fields)?,
+        data_type => {
+            let err = ArrowError::SchemaError(format!(
+                "no Avro type for field {} of type {}",
+                field.name(),
+                data_type
+            ));
+            return Err(err.into());
+        }
+    };
+
+    // BigQuery arrays are never null, the items of an array cannot be either
+    if field.is_nullable() && !matches!(field.data_type(), DataType::List(_)) {
+        Ok(format!(r#"["null",{}]"#, avro_type))
+    } else {
+        Ok(avro_type)
+    }
+}
+
+/// The Avro schema (as JSON) of the rows of a read session, given their Arrow `schema`.
+pub(crate) fn avro_schema_json(schema: &Schema) -> Result<String, Error> {
+    record("__root__", schema.fields())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock::test_schema;
+
+    #[test]
+    fn test_schema_as_avro() {
+        assert_eq!(
+            avro_schema_json(&test_schema()).unwrap(),
+            r#"{"type":"record","name":"__root__","fields":[{"name":"id","type":"long"},{"name":"name","type":["null","string"]}]}"#
+        );
+    }
+}
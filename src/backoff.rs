@@ -0,0 +1,72 @@
+//! How long to wait between the attempts of the retry loops of this crate: resuming a
+//! stream (see [`max_resume_attempts`](crate::client::ReadSessionBuilder::max_resume_attempts))
+//! and creating a read session (see [`max_create_retries`](crate::client::ReadSessionBuilder::max_create_retries)).
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A strategy to space out retries, e.g. with jitter or a constant delay, or to stop
+/// retrying altogether when a circuit breaker of your own is open.
+pub trait Backoff: Send {
+    /// How long to wait before the `attempt`th retry (starting at 1) of a retry loop, or
+    /// `None` to give up, failing with the last error. Retry loops also give up once
+    /// their maximum number of attempts is reached.
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration>;
+}
+
+/// A [`Backoff`](Backoff) shared by all the retry loops of a read session, and possibly
+/// by several sessions.
+pub type SharedBackoff = Arc<Mutex<dyn Backoff>>;
+
+/// The default [`Backoff`](Backoff): delays start at `initial` and double with every
+/// attempt, up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExponentialBackoff {
+    /// The delay before the first retry. Defaults to 100ms.
+    pub initial: Duration,
+    /// The longest delay. Defaults to 10s.
+    pub max: Duration,
+}
+
+impl Default for ExponentialBackoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Backoff for ExponentialBackoff {
+    fn next_delay(&mut self, attempt: u32) -> Option<Duration> {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.initial.checked_mul(factor).unwrap_or(self.max);
+        Some(delay.min(self.max))
+    }
+}
+
+/// The default [`SharedBackoff`](SharedBackoff).
+pub(crate) fn default_backoff() -> SharedBackoff {
+    Arc::new(Mutex::new(ExponentialBackoff::default()))
+}
+
+/// Ask `backoff` how long to wait before the `attempt`th retry.
+pub(crate) fn next_delay(backoff: &SharedBackoff, attempt: u32) -> Option<Duration> {
+    backoff.lock().unwrap().next_delay(attempt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff() {
+        let mut backoff = ExponentialBackoff::default();
+        let delays: Vec<_> = (1..=9)
+            .map(|attempt| backoff.next_delay(attempt).unwrap())
+            .collect();
+        assert_eq!(delays[0], Duration::from_millis(100));
+        assert_eq!(delays[1], Duration::from_millis(200));
+        assert_eq!(delays[7], Duration::from_secs(10));
+        assert_eq!(backoff.next_delay(u32::MAX), Some(Duration::from_secs(10)));
+    }
+}
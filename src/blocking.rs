@@ -0,0 +1,263 @@
+//! A blocking facade over the asynchronous [`Client`](crate::client::Client), for when
+//! setting up an async runtime is not worth the trouble (e.g. in quick scripts).
+//!
+//! Every call is driven to completion on an internal, single-threaded Tokio runtime.
+//! As such, nothing in this module should be called from within an async context.
+//! # Example
+//! ```rust
+//! use bigquery_storage::Table;
+//! use bigquery_storage::blocking::Client;
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     // 1. Create a Client, giving it the means to build an Authenticator
+//!     let mut client = Client::with_auth(|| async {
+//!         let sa_key = yup_oauth2::read_service_account_key("clientsecret.json").await?;
+//!         yup_oauth2::ServiceAccountAuthenticator::builder(sa_key)
+//!             .build()
+//!             .await
+//!     })?;
+//!
+//!     // 2. Create a ReadSession and read all of its streams
+//!     let test_table = Table::new(
+//!         "bigquery-public-data",
+//!         "london_bicycles",
+//!         "cycle_stations"
+//!     );
+//!
+//!     let record_batches = client
+//!         .read_session_with(test_table, |builder| {
+//!             builder.parent_project_id("openquery-public-testing".to_string())
+//!         })?
+//!         .collect_all()?;
+//!
+//!     Ok(())
+//! }
+//! ```
+use std::future::Future;
+
+use hyper::client::connect::Connect;
+use tokio::runtime::Runtime;
+use yup_oauth2::authenticator::Authenticator;
+
+#[cfg(feature = "arrow")]
+use arrow::datatypes::SchemaRef;
+#[cfg(feature = "arrow")]
+use arrow::error::ArrowError;
+#[cfg(feature = "arrow")]
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+#[cfg(feature = "arrow")]
+use futures::stream::{BoxStream, StreamExt};
+
+use crate::client::{
+    Client as AsyncClient, ReadSession as AsyncReadSession, ReadSessionBuilder, Table,
+};
+#[cfg(feature = "arrow")]
+use crate::read::DefaultArrowStreamReader;
+use crate::read::RowsStreamReader as AsyncRowsStreamReader;
+use crate::Error;
+
+fn new_runtime() -> Result<Runtime, Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime)
+}
+
+/// The blocking counterpart of [`Client`](crate::client::Client).
+pub struct Client<C> {
+    inner: AsyncClient<C>,
+    runtime: Runtime,
+}
+
+impl<C> Client<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// Create a new client using `auth` as a token generator.
+    pub fn new(auth: Authenticator<C>) -> Result<Self, Error> {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(AsyncClient::new(auth))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Create a new client, using `auth` to build its token generator. Since building an
+    /// [`Authenticator`](yup_oauth2::authenticator::Authenticator) is asynchronous, this is
+    /// done on the client's internal runtime.
+    pub fn with_auth<F, Fut, E>(auth: F) -> Result<Self, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Authenticator<C>, E>>,
+        Error: From<E>,
+    {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(async {
+            let auth = auth().await?;
+            AsyncClient::new(auth).await
+        })?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Create a new [`ReadSession`](ReadSession) over `table`, with the default options.
+    pub fn read_session(&mut self, table: Table) -> Result<ReadSession<'_, C>, Error> {
+        self.read_session_with(table, |builder| builder)
+    }
+
+    /// Create a new [`ReadSession`](ReadSession) over `table`. The options of the session
+    /// are set by `configure`, which is given a fresh
+    /// [`ReadSessionBuilder`](crate::client::ReadSessionBuilder).
+    pub fn read_session_with<F>(
+        &mut self,
+        table: Table,
+        configure: F,
+    ) -> Result<ReadSession<'_, C>, Error>
+    where
+        F: FnOnce(ReadSessionBuilder<'_, C>) -> ReadSessionBuilder<'_, C>,
+    {
+        let Self { inner, runtime } = self;
+        let builder = configure(inner.read_session_builder(table));
+        let inner = runtime.block_on(builder.build())?;
+        Ok(ReadSession { inner, runtime })
+    }
+}
+
+/// The blocking counterpart of [`read_table`](crate::client::read_table).
+#[cfg(feature = "arrow")]
+pub fn read_table<C>(
+    auth: Authenticator<C>,
+    table: Table,
+    parent_project_id: &str,
+) -> Result<Vec<RecordBatch>, Error>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    new_runtime()?.block_on(crate::client::read_table(auth, table, parent_project_id))
+}
+
+/// The blocking counterpart of [`ReadSession`](crate::client::ReadSession).
+pub struct ReadSession<'a, C> {
+    inner: AsyncReadSession<'a, C>,
+    runtime: &'a Runtime,
+}
+
+impl<'a, C> ReadSession<'a, C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// Take the next stream in this read session. Returns `None` when all streams have been taken.
+    pub fn next_stream(&mut self) -> Result<Option<RowsStreamReader<'a>>, Error> {
+        let runtime = self.runtime;
+        let next = runtime.block_on(self.inner.next_stream())?;
+        Ok(next.map(|inner| RowsStreamReader { inner, runtime }))
+    }
+
+    /// Read all the remaining streams of this session, one after the other, and collect
+    /// their content.
+    #[cfg(feature = "arrow")]
+    pub fn collect_all(mut self) -> Result<Vec<RecordBatch>, Error> {
+        let mut record_batches = Vec::new();
+        while let Some(stream_reader) = self.next_stream()? {
+            for record_batch in stream_reader.into_arrow_reader()? {
+                record_batches.push(record_batch?);
+            }
+        }
+        Ok(record_batches)
+    }
+
+    /// Read all the remaining streams of this session, `concurrency` of them at a time,
+    /// into an Arrow [`RecordBatchReader`](arrow::record_batch::RecordBatchReader). See
+    /// [`ReadSession::into_stream`](crate::client::ReadSession::into_stream) for the
+    /// meaning of `preserve_order`.
+    #[cfg(feature = "arrow")]
+    pub fn into_batch_reader(
+        self,
+        concurrency: usize,
+        preserve_order: bool,
+    ) -> Result<BatchReader<'a>, Error> {
+        let schema = self.inner.arrow_schema()?;
+        let stream = self.inner.into_stream(concurrency, preserve_order);
+        BatchReader::new(schema, stream, self.runtime)
+    }
+}
+
+/// The blocking counterpart of [`RowsStreamReader`](crate::read::RowsStreamReader).
+pub struct RowsStreamReader<'a> {
+    inner: AsyncRowsStreamReader,
+    runtime: &'a Runtime,
+}
+
+impl<'a> RowsStreamReader<'a> {
+    /// Consume the entire stream into an Arrow [StreamReader](arrow::ipc::reader::StreamReader).
+    #[cfg(feature = "arrow")]
+    pub fn into_arrow_reader(self) -> Result<DefaultArrowStreamReader, Error> {
+        self.runtime.block_on(self.inner.into_arrow_reader())
+    }
+
+    /// Consume the stream into an Arrow [`RecordBatchReader`](arrow::record_batch::RecordBatchReader),
+    /// which decodes batches as they are received.
+    #[cfg(feature = "arrow")]
+    pub fn into_batch_reader(mut self) -> Result<BatchReader<'a>, Error> {
+        let schema = self.runtime.block_on(self.inner.peek_schema())?;
+        let stream = self.inner.into_stream()?;
+        BatchReader::new(schema, stream, self.runtime)
+    }
+
+    /// Get back the underlying asynchronous reader.
+    pub fn into_inner(self) -> AsyncRowsStreamReader {
+        self.inner
+    }
+}
+
+/// An Arrow [`RecordBatchReader`](arrow::record_batch::RecordBatchReader) over one or
+/// more streams of a read session, which can be handed to any Arrow-native sink (e.g. a
+/// Parquet writer).
+#[cfg(feature = "arrow")]
+pub struct BatchReader<'a> {
+    schema: SchemaRef,
+    first: Option<RecordBatch>,
+    stream: BoxStream<'a, Result<RecordBatch, Error>>,
+    runtime: &'a Runtime,
+}
+
+#[cfg(feature = "arrow")]
+impl<'a> BatchReader<'a> {
+    /// The schema of the reader is the one of the first batch, since options of the
+    /// reader (e.g. the field order) can change it. `schema` is only used when there are
+    /// no batches at all.
+    fn new(
+        schema: SchemaRef,
+        mut stream: BoxStream<'a, Result<RecordBatch, Error>>,
+        runtime: &'a Runtime,
+    ) -> Result<Self, Error> {
+        let first = runtime.block_on(stream.next()).transpose()?;
+        let schema = first.as_ref().map(|batch| batch.schema()).unwrap_or(schema);
+        Ok(Self {
+            schema,
+            first,
+            stream,
+            runtime,
+        })
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl<'a> Iterator for BatchReader<'a> {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(batch) = self.first.take() {
+            return Some(Ok(batch));
+        }
+        let next = self.runtime.block_on(self.stream.next())?;
+        Some(next.map_err(|err| match err {
+            Error::Arrow(err) => err,
+            err => ArrowError::ExternalError(Box::new(err)),
+        }))
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl<'a> RecordBatchReader for BatchReader<'a> {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
@@ -19,30 +19,256 @@
 //!     Ok(())
 //! }
 //! ```
+use std::sync::Arc;
+
 use hyper::client::connect::Connect;
 use yup_oauth2::authenticator::Authenticator;
 
 use prost_types::Timestamp;
-use tonic::metadata::MetadataValue;
-use tonic::transport::{Channel, ClientTlsConfig};
-use tonic::{Request, Streaming};
+use std::collections::HashMap;
+#[cfg(feature = "parquet")]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tonic::metadata::{AsciiMetadataValue, MetadataValue};
+
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+use tonic::{Code, Request, Status, Streaming};
 
+#[cfg(feature = "arrow")]
+use arrow::compute::concat_batches;
+#[cfg(feature = "arrow")]
+use arrow::datatypes::SchemaRef;
+#[cfg(feature = "arrow")]
+use arrow::record_batch::RecordBatch;
+#[cfg(feature = "arrow")]
+use futures::future::ready;
+#[cfg(feature = "arrow")]
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+use crate::backoff::{default_backoff, next_delay, SharedBackoff};
 use crate::googleapis::big_query_read_client::BigQueryReadClient;
 use crate::googleapis::{
-    read_session::{TableModifiers, TableReadOptions},
-    CreateReadSessionRequest, DataFormat, ReadRowsRequest, ReadRowsResponse,
-    ReadSession as BigQueryReadSession, ReadStream,
+    arrow_serialization_options::CompressionCodec,
+    read_session::{
+        table_read_options::{OutputFormatSerializationOptions, ResponseCompressionCodec},
+        Schema, TableModifiers, TableReadOptions,
+    },
+    ArrowSchema, ArrowSerializationOptions, CreateReadSessionRequest, DataFormat, ReadRowsRequest,
+    ReadRowsResponse, ReadSession as BigQueryReadSession, ReadStream,
+};
+#[cfg(feature = "arrow")]
+use crate::read::{
+    decode_schema, order_columns, strip_continuation_bytes, strip_schema_metadata,
+    unify_record_batches,
 };
+use crate::read::{Reopen, Resume, SessionStats};
+use crate::rpc::retry_delay;
+#[cfg(feature = "parquet")]
+use crate::sink::PartitionedParquetSink;
+#[cfg(feature = "arrow")]
+use crate::sink::RecordBatchSink;
 use crate::Error;
 use crate::RowsStreamReader;
 
-static API_ENDPOINT: &'static str = "https://bigquerystorage.googleapis.com";
-static API_DOMAIN: &'static str = "bigquerystorage.googleapis.com";
-static API_SCOPE: &'static str = "https://www.googleapis.com/auth/bigquery";
+/// A closure run on every request, see [`ClientBuilder::interceptor`](ClientBuilder::interceptor).
+type Interceptor = Arc<Mutex<dyn FnMut(&mut Request<()>) + Send>>;
+
+/// What to do when creating a read session would go over the rate set by
+/// [`Client::with_session_rate_limit`](Client::with_session_rate_limit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnRateLimit {
+    /// Wait until the session can be created.
+    Wait,
+    /// Fail with [`Error::SessionRateLimited`](crate::Error::SessionRateLimited).
+    Fail,
+}
+
+/// What to do with the columns denied by
+/// [`Client::with_column_denylist`](Client::with_column_denylist) that a read would expose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDeniedColumn {
+    /// Leave them out of the read, reading the other columns.
+    Drop,
+    /// Fail with [`Error::ForbiddenColumn`](crate::Error::ForbiddenColumn).
+    Fail,
+}
+
+/// Top-level columns that must never be read, see
+/// [`Client::with_column_denylist`](Client::with_column_denylist).
+struct ColumnDenylist {
+    columns: Vec<String>,
+    on_denied: OnDeniedColumn,
+}
+
+impl ColumnDenylist {
+    /// Whether the field at `path` (e.g. `address.city`) is, or is part of, a denied column.
+    fn denies(&self, path: &str) -> bool {
+        let column = path.split('.').next().unwrap_or(path);
+        self.columns.iter().any(|denied| denied == column)
+    }
+
+    /// The `selected_fields` to read instead, which must not be empty.
+    fn apply(&self, selected_fields: Vec<String>) -> Result<Vec<String>, Error> {
+        let (denied, allowed): (Vec<_>, Vec<_>) = selected_fields
+            .into_iter()
+            .partition(|field| self.denies(field));
+        match denied.into_iter().next() {
+            // Selecting no fields at all would read every column
+            Some(column) if self.on_denied == OnDeniedColumn::Fail || allowed.is_empty() => {
+                Err(Error::ForbiddenColumn { column })
+            }
+            _ => Ok(allowed),
+        }
+    }
+}
+
+/// The environment variables the project owning read sessions is taken from, in order,
+/// when it is not set explicitly.
+const PROJECT_ENV_VARS: &[&str] = &["GOOGLE_CLOUD_PROJECT", "GCLOUD_PROJECT"];
+
+/// The first non-empty value of [`PROJECT_ENV_VARS`](PROJECT_ENV_VARS), as given by `var`.
+fn project_from_env<F: Fn(&str) -> Option<String>>(var: F) -> Option<String> {
+    PROJECT_ENV_VARS
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|project| !project.is_empty())
+}
+
+/// A token bucket, holding up to a second worth of tokens.
+struct TokenBucket {
+    rate: f64,
+    on_limit: OnRateLimit,
+    /// Tokens are taken ahead of time when waiting, so this can be negative.
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, on_limit: OnRateLimit) -> Self {
+        Self {
+            rate,
+            on_limit,
+            tokens: rate.max(1.),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, returning how long to wait before using it.
+    fn take(&mut self) -> Result<Duration, Error> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.));
+        self.refilled_at = now;
+
+        if self.tokens < 1. && self.on_limit == OnRateLimit::Fail {
+            return Err(Error::SessionRateLimited {
+                sessions_per_second: self.rate,
+            });
+        }
+        self.tokens -= 1.;
+        if self.tokens >= 0. {
+            Ok(Duration::from_secs(0))
+        } else {
+            Ok(Duration::from_secs_f64(-self.tokens / self.rate))
+        }
+    }
+}
+
+static API_ENDPOINT: &str = "https://bigquerystorage.googleapis.com";
+static API_DOMAIN: &str = "bigquerystorage.googleapis.com";
+static API_SCOPE: &str = "https://www.googleapis.com/auth/bigquery";
+static API_CLIENT: &str = concat!("bigquery-storage-rs/", env!("CARGO_PKG_VERSION"));
+
+/// The domain of the regional endpoint serving tables located in `location`.
+fn regional_api_domain(location: &str) -> String {
+    format!(
+        "bigquerystorage.{}.rep.googleapis.com",
+        location.to_lowercase()
+    )
+}
+
+/// Recognize the error returned by the API when trying to read something which is not a
+/// plain table, returning the type of that something. This is an `INVALID_ARGUMENT` whose
+/// message names the type of the table. Anything else is left as is rather than guessed
+/// at, e.g. a message which only mentions a column called `view`.
+fn unsupported_table_type(status: &Status) -> Option<&'static str> {
+    const TABLE_TYPES: &[(&str, &str)] = &[
+        ("is a materialized view", "materialized view"),
+        ("is a view", "view"),
+        ("is an external table", "external table"),
+        ("from views", "view"),
+        ("from external tables", "external table"),
+    ];
+    if status.code() != Code::InvalidArgument {
+        return None;
+    }
+    let message = status.message().to_lowercase();
+    TABLE_TYPES
+        .iter()
+        .find(|(phrase, _)| message.contains(phrase))
+        .map(|(_, table_type)| *table_type)
+}
+
+/// Recognize the error returned by the API when the row restriction of a session is
+/// invalid, returning the part of the message about the restriction.
+fn invalid_row_restriction(status: &Status) -> Option<String> {
+    if status.code() != Code::InvalidArgument {
+        return None;
+    }
+    let message = status.message();
+    // ASCII lowercasing keeps the byte offsets of `message`
+    let lowercase = message.to_ascii_lowercase();
+    let start = lowercase
+        .find("row restriction")
+        .or_else(|| lowercase.find("row_restriction"))?;
+    Some(message[start..].to_string())
+}
+
+/// Recognize the failures of calls sent on a connection which was broken, e.g. closed by
+/// an intermediary after being idle. These come from the transport, not from the server.
+fn broken_connection(status: &Status) -> bool {
+    const BROKEN: &[&str] = &[
+        "broken pipe",
+        "connection reset",
+        "connection closed",
+        "connection error",
+        "transport error",
+    ];
+    let message = status.message().to_lowercase();
+    matches!(status.code(), Code::Unknown | Code::Unavailable)
+        && BROKEN.iter().any(|broken| message.contains(broken))
+}
+
+/// Recognize the errors returned by the API when the dataset of the table is not in the
+/// location of the session (e.g. `Not found: Dataset p:d was not found in location EU`).
+/// These are `NOT_FOUND` or `INVALID_ARGUMENT` errors whose message is about locations;
+/// anything else is left as is.
+fn location_mismatch(status: &Status) -> bool {
+    const MISMATCHES: &[&str] = &["in location", "different locations", "location mismatch"];
+    let message = status.message().to_lowercase();
+    matches!(status.code(), Code::NotFound | Code::InvalidArgument)
+        && MISMATCHES.iter().any(|mismatch| message.contains(mismatch))
+}
+
+/// Recognize a `PERMISSION_DENIED` error, returning the IAM permission it names, if any.
+fn missing_permission(status: &Status) -> Option<String> {
+    if status.code() != Code::PermissionDenied {
+        return None;
+    }
+    status
+        .message()
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .map(|word| word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .find(|word| word.starts_with("bigquery.") && word.matches('.').count() == 2)
+        .map(str::to_string)
+}
 
 /// A fully qualified BigQuery table. This requires a `project_id`, a `dataset_id`
 /// and a `table_id`. Only alphanumerical and underscores are allowed for `dataset_id`
 /// and `table_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Table {
     project_id: String,
     dataset_id: String,
@@ -69,6 +295,106 @@ impl std::fmt::Display for Table {
     }
 }
 
+/// The ID of a Google Cloud project, e.g. the project a read session is billed to (see
+/// [`ReadSessionBuilder::parent_project`](ReadSessionBuilder::parent_project)), as opposed
+/// to the project owning the data in a [`Table`](Table).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProjectId(String);
+
+impl ProjectId {
+    /// Validate `id`: it has to start with a lowercase letter, followed by lowercase
+    /// letters, digits or hyphens. Legacy domain-scoped IDs (e.g. `example.com:project`)
+    /// are accepted too.
+    pub fn new(id: &str) -> Result<Self, Error> {
+        let name = id.rsplit(':').next().unwrap_or(id);
+        let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && !name.ends_with('-')
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(Error::InvalidOption {
+                name: "project_id",
+                reason: format!("{:?} is not a valid project ID", id),
+            });
+        }
+        Ok(Self(id.to_string()))
+    }
+}
+
+impl std::fmt::Display for ProjectId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl AsRef<str> for ProjectId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The routing parameters of a request, sent in its `x-goog-request-params` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RequestParams(Vec<(&'static str, String)>);
+
+impl RequestParams {
+    fn for_create_read_session(req: &CreateReadSessionRequest) -> Self {
+        let table = req
+            .read_session
+            .as_ref()
+            .map(|read_session| read_session.table.clone())
+            .unwrap_or_default();
+        Self(vec![("read_session.table", table)])
+    }
+
+    fn for_read_rows(req: &ReadRowsRequest) -> Self {
+        Self(vec![("read_stream", req.read_stream.clone())])
+    }
+}
+
+impl std::fmt::Display for RequestParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "&")?;
+            }
+            write!(f, "{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The read options of a session, if any is set. Reading the whole table omits them
+/// altogether, rather than sending empty ones.
+fn table_read_options(
+    selected_fields: Option<Vec<String>>,
+    row_restriction: Option<String>,
+    response_compression_codec: Option<ResponseCompressionCodec>,
+    sample_percentage: Option<f64>,
+    arrow_buffer_compression: Option<CompressionCodec>,
+) -> Option<TableReadOptions> {
+    if selected_fields.is_none()
+        && row_restriction.is_none()
+        && response_compression_codec.is_none()
+        && sample_percentage.is_none()
+        && arrow_buffer_compression.is_none()
+    {
+        return None;
+    }
+    Some(TableReadOptions {
+        selected_fields: selected_fields.unwrap_or_default(),
+        row_restriction: row_restriction.unwrap_or_default(),
+        response_compression_codec: response_compression_codec.map(|codec| codec as i32),
+        sample_percentage,
+        output_format_serialization_options: arrow_buffer_compression.map(|codec| {
+            OutputFormatSerializationOptions::ArrowSerializationOptions(ArrowSerializationOptions {
+                buffer_compression: codec as i32,
+            })
+        }),
+    })
+}
+
 macro_rules! read_session_builder {
     {
         $(
@@ -81,6 +407,8 @@ macro_rules! read_session_builder {
             $(
                 $field: Option<$ty>,
             )*
+            /// See [`with_raw_session`](ReadSessionBuilder::with_raw_session).
+            raw_session: Option<BigQueryReadSession>,
         }
 
         /// A builder for [`ReadSession`](crate::client::ReadSession).
@@ -113,7 +441,8 @@ read_session_builder! {
     data_format: DataFormat,
     #[doc = "Sets the snapshot time of the table. If not set, interpreted as now."]
     snapshot_time: Timestamp,
-    #[doc = "Names of the fields in the table that should be read. If empty or not set, all fields will be read. If the specified field is a nested field, all the sub-fields in the field will be selected. The output field order is unrelated to the order of fields in selected_fields."]
+    #[doc = "Names of the fields in the table that should be read. If empty or not set, all fields will be read. If the specified field is a nested field, all the sub-fields in the field will be selected. The output field order is unrelated to the order of fields in selected_fields.\n"]
+    #[doc = "Sub-fields of `RECORD` columns are selected with dotted paths: with a column `address` of type `RECORD<city STRING, street STRING>`, selecting `address.city` reads a column `address` of type `RECORD<city STRING>`, while selecting `address` reads both sub-fields. Paths with empty segments (e.g. `address.` or `.city`) are rejected with [`Error::InvalidOption`](crate::Error::InvalidOption)."]
     selected_fields: Vec<String>,
     #[doc = "SQL text filtering statement, similar to a `WHERE` clause in a query. Aggregates are not supported.\n"]
     #[doc = "Examples: \n
@@ -125,165 +454,3472 @@ read_session_builder! {
     row_restriction: String,
     #[doc = "Max initial number of streams. If unset or zero, the server will provide a value of streams so as to produce reasonable throughput. Must be non-negative. The number of streams may be lower than the requested number, depending on the amount parallelism that is reasonable for the table. Error will be returned if the max count is greater than the current system max limit of 1,000."]
     max_stream_count: i32,
-    #[doc = "The request project that owns the session. If not set, defaults to the project owning the table to be read."]
+    #[doc = "The request project that owns the session, which is billed for the read. If not set, defaults to the first non-empty environment variable among `GOOGLE_CLOUD_PROJECT` and `GCLOUD_PROJECT`, as other Google Cloud tools do, and then to the project owning the table to be read."]
     parent_project_id: String,
+    #[doc = "Maximum number of bytes to download, across all the streams of the session. Once over this limit, reading fails with [`Error::ByteLimitExceeded`](crate::Error::ByteLimitExceeded). This counts the size of the serialized record batches, not the number of rows they contain. If not set, there is no limit."]
+    max_bytes: u64,
+    #[doc = "Sets the codec used by the server to compress the serialized rows, before they are sent over the wire. Rows are decompressed transparently when read. If not set, rows are not compressed."]
+    response_compression_codec: ResponseCompressionCodec,
+    #[doc = "The location (e.g. `eu` or `us-east1`) of the dataset owning the table. When set, all the requests of the session are sent to the regional endpoint for that location, instead of the global endpoint. This has no effect on clients built with a custom [`endpoint`](ClientBuilder::endpoint)."]
+    location: String,
+    #[doc = "Whether [`next_stream`](ReadSession::next_stream) hands out streams in the order the server listed them. If not set, streams are handed out in reverse order. Setting this makes reads reproducible, e.g. for golden-file tests."]
+    streams_in_order: bool,
+    #[doc = "Whether the columns of the record batches should follow the order of [`selected_fields`](ReadSessionBuilder::selected_fields), rather than the order chosen by the server. This costs a projection of every batch, and only applies to [`RowsStreamReader::into_stream`](crate::read::RowsStreamReader::into_stream) and the streams derived from it. Defaults to `false`."]
+    preserve_field_order: bool,
+    #[doc = "Names of the top-level fields in the table that should not be read, all the others being read. This cannot be used along with [`selected_fields`](ReadSessionBuilder::selected_fields).\n"]
+    #[doc = "The API only supports selecting fields, so this costs an extra read session, created by [`build`](ReadSessionBuilder::build) to look up the fields of the table. Requires the `arrow` feature."]
+    exclude_fields: Vec<String>,
+    #[doc = "How many consecutive times a stream is opened again, at the offset it stopped at, when the server fails it with `ABORTED` or `UNAVAILABLE`. A `FAILED_PRECONDITION` (e.g. once the session expired, see [`ReadSession::expire_time`](ReadSession::expire_time)) is returned right away as [`Error::Status`](crate::Error::Status), since only a new session can fix it. Attempts are spaced with the [`backoff`](ReadSessionBuilder::backoff), by default an exponential backoff starting at 100ms. This is transparent to the reader of the stream. Defaults to 0, i.e. streams are not resumed and such failures are returned as [`Error::Status`](crate::Error::Status)."]
+    max_resume_attempts: u32,
+    #[doc = "Only read a fraction (in `(0, 1]`) of the table, by keeping `ceil(fraction * stream_count)` of the streams the server prepared. Defaults to reading the whole table.\n"]
+    #[doc = "This is not a uniform sample of the rows: each stream covers a contiguous chunk of the table's storage, so rows that are stored together (e.g. in the same partition or cluster) are sampled together. Streams may also hold different numbers of rows, so the fraction of rows read can differ from `fraction`. Requesting more streams with `max_stream_count` makes the sample finer grained."]
+    sample_fraction: f64,
+    #[doc = "Whether reading a stream which returns no rows at all fails, with [`Error::EmptyStream`](crate::Error::EmptyStream) naming the stream, e.g. to catch a partition that should hold data in a scheduled job. This is checked once the stream is exhausted, and only applies to [`RowsStreamReader::into_stream`](crate::read::RowsStreamReader::into_stream) and the streams derived from it. Defaults to `false`."]
+    strict_nonempty_streams: bool,
+    #[doc = "Only read a sample of `percentage` (in `(0, 100]`) percent of the table, picked by the server. Unlike [`sample_fraction`](ReadSessionBuilder::sample_fraction), this samples the storage blocks of the table rather than the streams of the session, and the streams only read the sampled rows. Defaults to reading the whole table."]
+    sample_percentage: f64,
+    #[doc = "Sets the codec used by the server to compress the buffers of the Arrow record batches (`LZ4_FRAME` or `ZSTD`), as defined by the Arrow IPC format. Unlike [`response_compression_codec`](ReadSessionBuilder::response_compression_codec), batches are decompressed as they are decoded. If not set, buffers are not compressed."]
+    arrow_buffer_compression: CompressionCodec,
+    #[doc = "How many times creating the session is retried when it fails with a retriable error (see [`Error::is_retriable`](crate::Error::is_retriable)), e.g. `RESOURCE_EXHAUSTED` when over quota. Retries wait for the delay the server suggests (as a `google.rpc.RetryInfo`) if any, or else with the [`backoff`](ReadSessionBuilder::backoff), by default an exponential backoff starting at 100ms. Defaults to 0, i.e. no retries."]
+    max_create_retries: u32,
+    #[doc = "Whether to drop the metadata of the Arrow schema of the session and of its fields, nested ones included, e.g. to save memory on wide tables with heavily annotated columns. The API has no way to request a schema without metadata: it is stripped once decoded, from [`ReadSession::arrow_schema`](ReadSession::arrow_schema) and from the batches of [`RowsStreamReader::into_stream`](crate::read::RowsStreamReader::into_stream) and the streams derived from it. Defaults to `false`."]
+    strip_schema_metadata: bool,
+    #[doc = "How long to wait between the attempts at creating the session (see [`max_create_retries`](ReadSessionBuilder::max_create_retries)) and at resuming its streams (see [`max_resume_attempts`](ReadSessionBuilder::max_resume_attempts)), e.g. `Arc::new(Mutex::new(ExponentialBackoff::default()))`. The backoff is shared by all these retry loops, and can be shared with other sessions. A delay suggested by the server takes precedence when creating the session. Defaults to an [`ExponentialBackoff`](crate::backoff::ExponentialBackoff)."]
+    backoff: SharedBackoff,
 }
 
 impl<'a, C> ReadSessionBuilder<'a, C>
 where
     C: Connect + Clone + Send + Sync + 'static,
 {
-    /// Build the [`ReadSession`](ReadSession). This will hit Google's API and
-    /// prepare the desired read streams.
-    pub async fn build(self) -> Result<ReadSession<'a, C>, Error> {
-        let table = self.table.to_string();
+    /// Tune the session for reading a whole table as fast as possible, at the cost of the
+    /// time to the first row. This sets:
+    /// - [`max_stream_count`](ReadSessionBuilder::max_stream_count) to 0, letting the
+    ///   server pick as many streams as it finds useful for throughput,
+    /// - [`response_compression_codec`](ReadSessionBuilder::response_compression_codec)
+    ///   to LZ4, trading some CPU for less bandwidth.
+    ///
+    /// Options set after this one override it.
+    pub fn throughput_mode(mut self) -> Self {
+        self.opts.max_stream_count = Some(0);
+        self.opts.response_compression_codec = Some(ResponseCompressionCodec::Lz4);
+        self
+    }
+
+    /// Tune the session for getting the first rows as soon as possible, at the cost of
+    /// throughput, e.g. for previews. This sets:
+    /// - [`max_stream_count`](ReadSessionBuilder::max_stream_count) to 1, so that the
+    ///   rows are not spread over streams which would all have to be opened,
+    /// - [`response_compression_codec`](ReadSessionBuilder::response_compression_codec)
+    ///   back to none, so that batches can be decoded as soon as they are received.
+    ///
+    /// Options set after this one override it.
+    pub fn latency_mode(mut self) -> Self {
+        self.opts.max_stream_count = Some(1);
+        self.opts.response_compression_codec = None;
+        self
+    }
+
+    /// Build the session in [`latency_mode`](ReadSessionBuilder::latency_mode), and read
+    /// its first `max_rows` rows, see [`ReadSession::preview`](ReadSession::preview).
+    #[cfg(feature = "arrow")]
+    pub async fn preview(self, max_rows: usize) -> Result<RecordBatch, Error> {
+        self.latency_mode().build().await?.preview(max_rows).await
+    }
+
+    /// Start the requested session from `session`, rather than from an empty one, e.g. to
+    /// set fields of the API which this builder has no option for yet. The table of the
+    /// session is always the one of the builder. The options set on this builder take
+    /// precedence over the fields of `session`, which in turn take precedence over the
+    /// defaults of this crate (e.g. the Arrow data format).
+    pub fn with_raw_session(mut self, session: BigQueryReadSession) -> Self {
+        self.opts.raw_session = Some(session);
+        self
+    }
+
+    /// Set the project billed for the session and the [`location`](ReadSessionBuilder::location)
+    /// of the dataset of the table at once, for setups where the billing project, the
+    /// project of the table and the location must all be consistent. When the server
+    /// rejects the session because they are not, [`build`](ReadSessionBuilder::build)
+    /// fails with [`Error::ProjectLocationMismatch`](crate::Error::ProjectLocationMismatch),
+    /// which names all three.
+    pub fn project_and_location(self, billing_project: ProjectId, location: &str) -> Self {
+        self.parent_project(billing_project)
+            .location(location.to_string())
+    }
+
+    /// Like [`parent_project_id`](ReadSessionBuilder::parent_project_id), with an ID which
+    /// was already validated.
+    pub fn parent_project(self, project: ProjectId) -> Self {
+        self.parent_project_id(project.0)
+    }
+
+    /// Sets the [`snapshot_time`](ReadSessionBuilder::snapshot_time) to the one labelled
+    /// `label` in `snapshots`, e.g. a catalog of reads kept in your own metadata store, so
+    /// that reads can be reproduced by name. Fails if `label` is not in `snapshots`.
+    ///
+    /// The Storage API has no notion of named snapshots of its own. For a snapshot managed
+    /// by BigQuery, create a [table snapshot](https://cloud.google.com/bigquery/docs/table-snapshots-intro)
+    /// and read it like any other [`Table`](Table).
+    pub fn named_snapshot(
+        self,
+        snapshots: &HashMap<String, Timestamp>,
+        label: &str,
+    ) -> Result<Self, Error> {
+        match snapshots.get(label) {
+            Some(snapshot_time) => Ok(self.snapshot_time(snapshot_time.clone())),
+            None => Err(Error::InvalidOption {
+                name: "named_snapshot",
+                reason: format!("unknown snapshot {}", label),
+            }),
+        }
+    }
+
+    /// The project owning the session, see
+    /// [`parent_project_id`](ReadSessionBuilder::parent_project_id).
+    fn resolved_parent_project_id(&self) -> String {
+        self.opts
+            .parent_project_id
+            .clone()
+            .or_else(|| project_from_env(|name| std::env::var(name).ok()))
+            .unwrap_or_else(|| self.table.project_id.clone())
+    }
+
+    /// The top-level fields of the table, but the `excluded` ones.
+    async fn fields_excluding(&mut self, excluded: &[String]) -> Result<Vec<String>, Error> {
+        let fields = self.table_fields("exclude_fields").await?;
+        if let Some(unknown) = excluded.iter().find(|name| !fields.contains(name)) {
+            return Err(Error::InvalidOption {
+                name: "exclude_fields",
+                reason: format!("{:?} is not a top-level field of {}", unknown, self.table),
+            });
+        }
+        Ok(fields
+            .into_iter()
+            .filter(|name| !excluded.contains(name))
+            .collect())
+    }
 
-        let mut inner = BigQueryReadSession {
-            table,
+    /// The top-level fields of the table, looked up on behalf of the `option` which needs
+    /// them. The API has no way to get the schema of a table other than creating a read
+    /// session.
+    #[cfg(feature = "arrow")]
+    async fn table_fields(&mut self, _option: &'static str) -> Result<Vec<String>, Error> {
+        let mut read_session = BigQueryReadSession {
+            table: self.table.to_string(),
+            ..Default::default()
+        };
+        read_session.set_data_format(DataFormat::Arrow);
+        if let Some(snapshot_time) = &self.opts.snapshot_time {
+            read_session.table_modifiers = Some(TableModifiers {
+                snapshot_time: Some(snapshot_time.clone()),
+            });
+        }
+        let parent_project_id = self.resolved_parent_project_id();
+        let req = CreateReadSessionRequest {
+            parent: format!("projects/{}", parent_project_id),
+            read_session: Some(read_session),
+            max_stream_count: 1,
             ..Default::default()
         };
 
-        let data_format = self.opts.data_format.unwrap_or(DataFormat::Arrow);
-        inner.set_data_format(data_format);
+        let read_session = self
+            .client
+            .raw_create_read_session(req, self.opts.location.as_deref())
+            .await?;
+        let serialized_schema = match read_session.schema {
+            Some(Schema::ArrowSchema(ArrowSchema { serialized_schema })) => serialized_schema,
+            _ => return Err(Error::MissingSchema {}),
+        };
+        let schema = decode_schema(strip_continuation_bytes(&serialized_schema)?)?;
+
+        Ok(schema
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect())
+    }
+
+    #[cfg(not(feature = "arrow"))]
+    async fn table_fields(&mut self, option: &'static str) -> Result<Vec<String>, Error> {
+        Err(Error::InvalidOption {
+            name: option,
+            reason: "requires the arrow feature".to_string(),
+        })
+    }
+
+    /// Check the options which can be checked without calling the API.
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(fraction) = self.opts.sample_fraction {
+            if !(fraction > 0. && fraction <= 1.) {
+                return Err(Error::InvalidOption {
+                    name: "sample_fraction",
+                    reason: format!("{} is not in (0, 1]", fraction),
+                });
+            }
+        }
+
+        if let Some(percentage) = self.opts.sample_percentage {
+            if !(percentage > 0. && percentage <= 100.) {
+                return Err(Error::InvalidOption {
+                    name: "sample_percentage",
+                    reason: format!("{} is not in (0, 100]", percentage),
+                });
+            }
+        }
 
-        if let Some(snapshot_time) = self.opts.snapshot_time {
-            inner.table_modifiers = Some(TableModifiers {
-                snapshot_time: Some(snapshot_time),
+        if let Some(location) = &self.opts.location {
+            let valid = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-';
+            if location.is_empty() || !location.to_ascii_lowercase().chars().all(valid) {
+                return Err(Error::InvalidOption {
+                    name: "location",
+                    reason: format!("{:?} is not a location, e.g. `eu` or `us-east1`", location),
+                });
+            }
+        }
+
+        if self.opts.exclude_fields.is_some() && self.opts.selected_fields.is_some() {
+            return Err(Error::InvalidOption {
+                name: "exclude_fields",
+                reason: "cannot be used along with selected_fields".to_string(),
             });
         }
 
-        let mut tro = TableReadOptions::default();
-        if let Some(selected_fields) = self.opts.selected_fields {
-            tro.selected_fields = selected_fields;
+        if let Some(selected_fields) = &self.opts.selected_fields {
+            if let Some(field) = selected_fields
+                .iter()
+                .find(|field| field.split('.').any(str::is_empty))
+            {
+                return Err(Error::InvalidOption {
+                    name: "selected_fields",
+                    reason: format!("{:?} is not a valid field path", field),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The request creating the session, once the options are validated.
+    fn request(&self) -> CreateReadSessionRequest {
+        let mut inner = self.opts.raw_session.clone().unwrap_or_default();
+        inner.table = self.table.to_string();
+
+        match self.opts.data_format {
+            Some(data_format) => inner.set_data_format(data_format),
+            None if inner.data_format() == DataFormat::Unspecified => {
+                inner.set_data_format(DataFormat::Arrow)
+            }
+            None => {}
         }
 
-        if let Some(row_restriction) = self.opts.row_restriction {
-            tro.row_restriction = row_restriction;
+        if let Some(snapshot_time) = &self.opts.snapshot_time {
+            let table_modifiers = inner
+                .table_modifiers
+                .get_or_insert_with(TableModifiers::default);
+            table_modifiers.snapshot_time = Some(snapshot_time.clone());
         }
 
-        let parent_project_id = self.opts.parent_project_id.unwrap_or(self.table.project_id);
+        let read_options = table_read_options(
+            self.opts.selected_fields.clone(),
+            self.opts.row_restriction.clone(),
+            self.opts.response_compression_codec,
+            self.opts.sample_percentage,
+            self.opts.arrow_buffer_compression,
+        );
+        inner.read_options = match (inner.read_options.take(), read_options) {
+            // Only the options set on this builder override those of the raw session
+            (Some(mut base), Some(read_options)) => {
+                if self.opts.selected_fields.is_some() {
+                    base.selected_fields = read_options.selected_fields;
+                }
+                if self.opts.row_restriction.is_some() {
+                    base.row_restriction = read_options.row_restriction;
+                }
+                if self.opts.response_compression_codec.is_some() {
+                    base.response_compression_codec = read_options.response_compression_codec;
+                }
+                if self.opts.sample_percentage.is_some() {
+                    base.sample_percentage = read_options.sample_percentage;
+                }
+                if self.opts.arrow_buffer_compression.is_some() {
+                    base.output_format_serialization_options =
+                        read_options.output_format_serialization_options;
+                }
+                Some(base)
+            }
+            (base, read_options) => read_options.or(base),
+        };
+
+        let parent_project_id = self.resolved_parent_project_id();
         let parent = format!("projects/{}", parent_project_id);
         let max_stream_count = self.opts.max_stream_count.unwrap_or_default();
 
-        let req = CreateReadSessionRequest {
+        CreateReadSessionRequest {
             parent,
             read_session: Some(inner),
             max_stream_count,
+            ..Default::default()
+        }
+    }
+
+    /// The request [`build`](ReadSessionBuilder::build) would send, without sending it,
+    /// e.g. to test the options of a builder or to send the request later with
+    /// [`Client::execute_read_session`](Client::execute_read_session).
+    ///
+    /// The options only applied by this crate once the session is created (e.g.
+    /// [`sample_fraction`](ReadSessionBuilder::sample_fraction)) are not part of the
+    /// request. Fails if [`exclude_fields`](ReadSessionBuilder::exclude_fields) is set,
+    /// or if the client has a [column denylist](Client::with_column_denylist) and no
+    /// [`selected_fields`](ReadSessionBuilder::selected_fields) are set, since resolving
+    /// them calls the API.
+    pub fn build_request(mut self) -> Result<CreateReadSessionRequest, Error> {
+        self.validate()?;
+        if self.opts.exclude_fields.is_some() {
+            return Err(Error::InvalidOption {
+                name: "exclude_fields",
+                reason: "requires a call to the API, use build instead".to_string(),
+            });
+        }
+        if let Some(denylist) = self.client.column_denylist.clone() {
+            let selected_fields = match self.opts.selected_fields.take() {
+                Some(fields) if !fields.is_empty() => fields,
+                _ => {
+                    return Err(Error::InvalidOption {
+                        name: "column_denylist",
+                        reason: "reading all the columns requires a call to the API, use build \
+                                 instead"
+                            .to_string(),
+                    })
+                }
+            };
+            self.opts.selected_fields = Some(denylist.apply(selected_fields)?);
+        }
+        Ok(self.request())
+    }
+
+    /// Build the [`ReadSession`](ReadSession). This will hit Google's API and
+    /// prepare the desired read streams.
+    pub async fn build(mut self) -> Result<ReadSession<'a, C>, Error> {
+        self.validate()?;
+
+        if let Some(excluded) = self.opts.exclude_fields.take() {
+            let selected_fields = self.fields_excluding(&excluded).await?;
+            self.opts.selected_fields = Some(selected_fields);
+        }
+
+        if let Some(denylist) = self.client.column_denylist.clone() {
+            let selected_fields = match self.opts.selected_fields.take() {
+                Some(fields) if !fields.is_empty() => fields,
+                _ => self.table_fields("column_denylist").await?,
+            };
+            self.opts.selected_fields = Some(denylist.apply(selected_fields)?);
+        }
+
+        let table = self.table.to_string();
+        let req = self.request();
+
+        let field_order = match (self.opts.preserve_field_order, &self.opts.selected_fields) {
+            (Some(true), Some(selected_fields)) => Some(selected_fields.clone()),
+            _ => None,
+        };
+
+        let has_row_restriction = self.opts.row_restriction.is_some();
+        let billing_project = self.resolved_parent_project_id();
+        let data_project = self.table.project_id.clone();
+
+        let location = self.opts.location;
+        let backoff = self.opts.backoff.unwrap_or_else(default_backoff);
+        let max_create_retries = self.opts.max_create_retries.unwrap_or_default();
+        let mut retries = 0;
+        let created = loop {
+            let created = self
+                .client
+                .raw_create_read_session(req.clone(), location.as_deref())
+                .await;
+            match created {
+                Err(err) if err.is_retriable() && retries < max_create_retries => {
+                    retries += 1;
+                    let delay = match &err {
+                        Error::Status(status) => retry_delay(status),
+                        _ => None,
+                    };
+                    match delay.or_else(|| next_delay(&backoff, retries)) {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => break Err(err),
+                    }
+                }
+                created => break created,
+            }
         };
+        let mut inner = created.map_err(|err| match err {
+            Error::Status(status) => {
+                if let Some(reason) =
+                    invalid_row_restriction(&status).filter(|_| has_row_restriction)
+                {
+                    Error::InvalidRowRestriction { reason }
+                } else if let Some(table_type) = unsupported_table_type(&status) {
+                    Error::UnsupportedTableType { table, table_type }
+                } else if let Some(location) =
+                    location.clone().filter(|_| location_mismatch(&status))
+                {
+                    Error::ProjectLocationMismatch {
+                        billing_project,
+                        data_project,
+                        location,
+                        status,
+                    }
+                } else {
+                    Error::Status(status)
+                }
+            }
+            err => err,
+        })?;
+        let stream_names = inner
+            .streams
+            .iter()
+            .map(|stream| stream.name.clone())
+            .collect();
+        if let Some(fraction) = self.opts.sample_fraction {
+            let sampled = (fraction * inner.streams.len() as f64).ceil() as usize;
+            inner.streams.truncate(sampled);
+        }
+        if self.opts.streams_in_order.unwrap_or_default() {
+            // Streams are popped from the back
+            inner.streams.reverse();
+        }
 
-        let inner = self.client.create_read_session(req).await?;
+        let stats = Arc::new(SessionStats::new(self.opts.max_bytes));
 
         Ok(ReadSession {
-            client: self.client,
+            client: ClientHandle::Borrowed(self.client),
             inner,
+            location,
+            stream_names,
+            stats,
+            field_order,
+            max_resume_attempts: self.opts.max_resume_attempts.unwrap_or_default(),
+            strict_nonempty_streams: self.opts.strict_nonempty_streams.unwrap_or_default(),
+            #[cfg(feature = "arrow")]
+            known_schema: None,
+            strip_schema_metadata: self.opts.strip_schema_metadata.unwrap_or_default(),
+            backoff,
+            stream_offsets: HashMap::new(),
         })
     }
 }
 
+/// A builder for a group of [`ReadSession`](ReadSession)s over several tables, all read
+/// as of the same snapshot time. The rows of the sessions are therefore mutually
+/// consistent, e.g. for tables to be joined after they are read.
+pub struct SessionGroupBuilder<'a, C> {
+    client: &'a mut Client<C>,
+    tables: Vec<Table>,
+    snapshot_time: Option<Timestamp>,
+    parent_project_id: Option<String>,
+}
+
+impl<'a, C> SessionGroupBuilder<'a, C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn new(client: &'a mut Client<C>) -> Self {
+        Self {
+            client,
+            tables: Vec::new(),
+            snapshot_time: None,
+            parent_project_id: None,
+        }
+    }
+
+    /// Add `table` to the group.
+    pub fn table(mut self, table: Table) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    /// The snapshot time shared by all the sessions. If not set, this is the snapshot time
+    /// the server picks for the first session, see
+    /// [`ReadSession::snapshot_time`](ReadSession::snapshot_time).
+    pub fn snapshot_time(mut self, snapshot_time: Timestamp) -> Self {
+        self.snapshot_time = Some(snapshot_time);
+        self
+    }
+
+    /// The request project that owns the sessions. If not set, each session is owned by
+    /// the project given by the environment, or else by the project of its table, see
+    /// [`ReadSessionBuilder::parent_project_id`](ReadSessionBuilder::parent_project_id).
+    pub fn parent_project_id(mut self, parent_project_id: String) -> Self {
+        self.parent_project_id = Some(parent_project_id);
+        self
+    }
+
+    /// Create a read session for every table of the group. Sessions are created one after
+    /// the other, and given their own copy of the client.
+    pub async fn build(self) -> Result<HashMap<Table, OwnedReadSession<C>>, Error> {
+        let mut snapshot_time = self.snapshot_time;
+
+        let mut sessions = HashMap::with_capacity(self.tables.len());
+        for table in self.tables {
+            let mut builder = self.client.read_session_builder(table.clone());
+            if let Some(snapshot_time) = &snapshot_time {
+                builder = builder.snapshot_time(snapshot_time.clone());
+            }
+            if let Some(parent_project_id) = &self.parent_project_id {
+                builder = builder.parent_project_id(parent_project_id.clone());
+            }
+            let read_session = builder.build().await?.into_owned();
+            // The clock of the server, rather than the local one, decides what "now" is
+            if snapshot_time.is_none() {
+                let reported = read_session.snapshot_time().cloned().ok_or_else(|| {
+                    Error::InvalidResponse(
+                        "the server did not report the snapshot time of the session".to_string(),
+                    )
+                })?;
+                snapshot_time = Some(reported);
+            }
+            sessions.insert(table, read_session);
+        }
+        Ok(sessions)
+    }
+}
+
+/// The [`Client`](Client) a [`ReadSession`](ReadSession) makes its requests with.
+enum ClientHandle<'a, C> {
+    Borrowed(&'a mut Client<C>),
+    Owned(Box<Client<C>>),
+}
+
+impl<'a, C> std::ops::Deref for ClientHandle<'a, C> {
+    type Target = Client<C>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(client) => client,
+            Self::Owned(client) => client,
+        }
+    }
+}
+
+impl<'a, C> std::ops::DerefMut for ClientHandle<'a, C> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Borrowed(client) => client,
+            Self::Owned(client) => client,
+        }
+    }
+}
+
 /// A practical wrapper around a [BigQuery Storage read session](https://cloud.google.com/bigquery/docs/reference/storage#create_a_session).
 /// Do not create it manually, use [`Client::read_session_builder`](Client::read_session_builder) instead.
+///
+/// A `ReadSession` borrows the [`Client`](Client) it was built with. Use
+/// [`into_owned`](ReadSession::into_owned) to get an [`OwnedReadSession`](OwnedReadSession)
+/// that can outlive it.
+///
+/// The API has no way to close a session before it expires (see
+/// [`expire_time`](ReadSession::expire_time)), so dropping a `ReadSession` does not make
+/// any call. Sessions left over are not billed, as reads are billed on the bytes actually
+/// scanned, but they do count towards the quota of sessions created per project: services
+/// creating many short-lived sessions should size that quota accordingly.
+///
+/// The Storage API does not describe how tables are partitioned or clustered. Look it up
+/// with the [`tables.get`](https://cloud.google.com/bigquery/docs/reference/rest/v2/tables/get)
+/// method of the BigQuery API (`timePartitioning.field`, `rangePartitioning.field` and
+/// `clustering.fields`): filtering on these columns in a
+/// [`row_restriction`](ReadSessionBuilder::row_restriction) prunes the storage scanned.
 pub struct ReadSession<'a, C> {
-    client: &'a mut Client<C>,
+    client: ClientHandle<'a, C>,
     inner: BigQueryReadSession,
+    location: Option<String>,
+    /// The names of all the streams of the session, in the order the server listed them.
+    stream_names: Vec<String>,
+    stats: Arc<SessionStats>,
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    field_order: Option<Vec<String>>,
+    max_resume_attempts: u32,
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    strict_nonempty_streams: bool,
+    #[cfg(feature = "arrow")]
+    known_schema: Option<SchemaRef>,
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    strip_schema_metadata: bool,
+    backoff: SharedBackoff,
+    /// The offsets streams handed out by [`next_stream`](ReadSession::next_stream) are
+    /// opened at, if not 0, see [`drain_with_deadline`](ReadSession::drain_with_deadline).
+    stream_offsets: HashMap<String, i64>,
+}
+
+/// What [`ReadSession::drain_with_deadline`](ReadSession::drain_with_deadline) read
+/// before its deadline.
+#[cfg(feature = "arrow")]
+#[derive(Debug, Clone)]
+pub struct DrainProgress {
+    /// The batches read, in no particular order.
+    pub batches: Vec<RecordBatch>,
+    /// The streams not read to the end, with the offset to resume them at, e.g. with
+    /// [`stream_at`](ReadSession::stream_at).
+    pub offsets: HashMap<String, i64>,
+}
+
+#[cfg(feature = "arrow")]
+impl DrainProgress {
+    /// Whether all the streams of the session were read to the end.
+    pub fn is_complete(&self) -> bool {
+        self.offsets.is_empty()
+    }
 }
 
+/// The rows added to and removed from a table between two snapshots, see
+/// [`Client::diff_snapshots`](Client::diff_snapshots).
+#[cfg(feature = "arrow")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotDiff {
+    /// The rows of the later snapshot with a key which is not in the earlier one.
+    pub added: RecordBatch,
+    /// The rows of the earlier snapshot with a key which is not in the later one.
+    pub removed: RecordBatch,
+}
+
+/// What a [`ReadSession`](ReadSession) is about to read, as reported by the server when
+/// the session was created. See [`ReadSession::summary`](ReadSession::summary).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadSessionSummary {
+    /// See [`ReadSession::stream_count`](ReadSession::stream_count).
+    pub stream_count: usize,
+    /// The number of rows the server estimates the session will read.
+    pub estimated_rows: u64,
+    /// The number of bytes the server estimates the session will scan, which is what the
+    /// read is billed on.
+    pub estimated_bytes: u64,
+    /// See [`ReadSession::expire_time`](ReadSession::expire_time).
+    pub expire_time: Option<Timestamp>,
+    /// See [`ReadSession::snapshot_time`](ReadSession::snapshot_time).
+    pub snapshot_time: Option<Timestamp>,
+    /// A hash of the serialized schema of the session, to tell whether two sessions read
+    /// rows of the same schema. This is stable across runs and versions of this crate.
+    pub schema_fingerprint: u64,
+}
+
+/// The 64 bits FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// A [`ReadSession`](ReadSession) that owns its [`Client`](Client), and can therefore be
+/// moved around freely.
+pub type OwnedReadSession<C> = ReadSession<'static, C>;
+
 impl<'a, C> ReadSession<'a, C>
 where
     C: Connect + Clone + Send + Sync + 'static,
 {
+    /// Detach this session from the [`Client`](Client) it borrows, by giving it its own
+    /// copy of the client. Clients are cheap to clone: the underlying connection and
+    /// token generator are shared.
+    pub fn into_owned(self) -> OwnedReadSession<C> {
+        let client = match self.client {
+            ClientHandle::Borrowed(client) => Box::new(client.clone()),
+            ClientHandle::Owned(client) => client,
+        };
+        ReadSession {
+            client: ClientHandle::Owned(client),
+            inner: self.inner,
+            location: self.location,
+            stream_names: self.stream_names,
+            stats: self.stats,
+            field_order: self.field_order,
+            max_resume_attempts: self.max_resume_attempts,
+            strict_nonempty_streams: self.strict_nonempty_streams,
+            #[cfg(feature = "arrow")]
+            known_schema: self.known_schema,
+            strip_schema_metadata: self.strip_schema_metadata,
+            backoff: self.backoff,
+            stream_offsets: self.stream_offsets,
+        }
+    }
+
     /// Take the next stream in this read session. Returns `None` when all streams have been taken.
+    ///
+    /// All the streams of a session are handed out by the server when the session is
+    /// created, up to its cap (1000 at the time of writing), so this does not make any
+    /// call besides opening the returned stream. The returned readers do not borrow the
+    /// session: they can be read concurrently while more streams are taken.
     pub async fn next_stream(&mut self) -> Result<Option<RowsStreamReader>, Error> {
         match self.inner.streams.pop() {
             Some(ReadStream { name }) => {
-                let rows_stream = self.client.read_stream_rows(&name).await?;
-                let schema = self
-                    .inner
-                    .schema
-                    .clone()
-                    .ok_or(Error::invalid("empty schema response"))?;
-                Ok(Some(RowsStreamReader::new(schema, rows_stream)))
+                let offset = self.stream_offsets.remove(&name).unwrap_or_default();
+                self.open_stream(name, offset).await.map(Some)
             }
             None => Ok(None),
         }
     }
-}
-
-/// The main object of this crate.
-pub struct Client<C> {
-    auth: Authenticator<C>,
-    big_query_read_client: BigQueryReadClient<Channel>,
-}
 
-impl<C> Client<C>
-where
-    C: Connect + Clone + Send + Sync + 'static,
-{
-    /// Create a new client using `auth` as a token generator.
-    pub async fn new(auth: Authenticator<C>) -> Result<Self, Error> {
-        let tls_config = ClientTlsConfig::new().domain_name(API_DOMAIN);
-        let channel = Channel::from_static(API_ENDPOINT)
-            .tls_config(tls_config)?
-            .connect()
-            .await?;
-        let big_query_read_client = BigQueryReadClient::new(channel);
-        Ok(Self {
-            auth,
-            big_query_read_client,
-        })
+    /// Like [`next_stream`](ReadSession::next_stream), but the stream comes with its
+    /// [`stream_index`](ReadSession::stream_index), e.g. to name the file it is written to
+    /// (`part-0000.parquet`, ...) in an export job.
+    pub async fn next_indexed_stream(
+        &mut self,
+    ) -> Result<Option<(usize, RowsStreamReader)>, Error> {
+        let reader = match self.next_stream().await? {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+        let index = self
+            .stream_index(reader.stream_name())
+            .expect("streams are all listed by the server");
+        Ok(Some((index, reader)))
     }
 
-    /// Create a new [`ReadSessionBuilder`](ReadSessionBuilder).
-    pub fn read_session_builder(&mut self, table: Table) -> ReadSessionBuilder<'_, C> {
-        ReadSessionBuilder::new(self, table)
-    }
-    async fn new_request<D>(&self, t: D, params: &str) -> Result<Request<D>, Error> {
-        let token = self.auth.token(&[API_SCOPE]).await?;
-        let bearer_token = format!("Bearer {}", token.as_str());
-        let bearer_value = MetadataValue::from_str(&bearer_token)?;
-        let mut req = Request::new(t);
-        let meta = req.metadata_mut();
-        meta.insert("authorization", bearer_value);
-        meta.insert("x-goog-request-params", MetadataValue::from_str(params)?);
-        Ok(req)
+    /// The position of the stream called `name` in the list of streams the server
+    /// prepared for this session, from 0 to [`stream_count`](ReadSession::stream_count)
+    /// (excluded), whatever the order streams are taken in. Returns `None` if the stream
+    /// is not part of this session.
+    ///
+    /// Indices are reproducible across runs as long as the server lists the streams in the
+    /// same order, which the API does not guarantee: at least, sessions should be created
+    /// with the same options, [`snapshot_time`](ReadSessionBuilder::snapshot_time) and a
+    /// fixed [`max_stream_count`](ReadSessionBuilder::max_stream_count).
+    pub fn stream_index(&self, name: &str) -> Option<usize> {
+        self.stream_names.iter().position(|stream| stream == name)
     }
-    async fn create_read_session(
-        &mut self,
-        req: CreateReadSessionRequest,
-    ) -> Result<BigQueryReadSession, Error> {
-        let table_uri = &req.read_session.as_ref().unwrap().table;
-        let params = format!("read_session.table={}", table_uri);
-        let wrapped = self.new_request(req, &params).await?;
 
-        let read_session = self
-            .big_query_read_client
-            .create_read_session(wrapped)
-            .await?
-            .into_inner();
-        Ok(read_session)
+    /// Take the stream called `name` in this read session, starting at row `offset`. This
+    /// is how a stream is resumed, e.g. by a worker owning a given stream and keeping
+    /// track of how many rows it has read. If not taken yet, the stream is removed from
+    /// the ones handed out by [`next_stream`](ReadSession::next_stream).
+    pub async fn stream_at(&mut self, name: &str, offset: i64) -> Result<RowsStreamReader, Error> {
+        self.inner.streams.retain(|stream| stream.name != name);
+        self.stream_offsets.remove(name);
+        self.open_stream(name.to_string(), offset).await
     }
-    async fn read_stream_rows(
+
+    /// Take the stream called `name` in this read session, reading at most `max_rows` rows
+    /// from row `offset` on. Giving each worker a different window (e.g. `[0, 1000)` and
+    /// `[1000, 2000)`) partitions a single stream between them, without overlaps. Like
+    /// [`stream_at`](ReadSession::stream_at), this removes the stream from the ones handed
+    /// out by [`next_stream`](ReadSession::next_stream).
+    #[cfg(feature = "arrow")]
+    pub async fn stream_window(
         &mut self,
-        stream: &str,
-    ) -> Result<Streaming<ReadRowsResponse>, Error> {
-        let req = ReadRowsRequest {
-            read_stream: stream.to_string(),
-            offset: 0, // TODO
+        name: &str,
+        offset: i64,
+        max_rows: usize,
+    ) -> Result<RowsStreamReader, Error> {
+        let reader = self.stream_at(name, offset).await?;
+        Ok(reader.limit_rows(max_rows))
+    }
+
+    async fn open_stream(&mut self, name: String, offset: i64) -> Result<RowsStreamReader, Error> {
+        let rows_stream = self
+            .client
+            .read_stream_rows(&name, offset, self.location.as_deref())
+            .await?;
+        let stats = self.stats.clone();
+        // The server may send the schema along the rows rather than with the session
+        let mut reader = match self.inner.schema.clone() {
+            Some(schema) => RowsStreamReader::new(name.clone(), schema, rows_stream, stats),
+            None => {
+                RowsStreamReader::with_schema_from_rows(name.clone(), rows_stream, stats).await?
+            }
+        };
+        if self.max_resume_attempts > 0 {
+            let client = (*self.client).clone();
+            let location = self.location.clone();
+            let reopen: Reopen = Box::new(move |offset| {
+                let mut client = client.clone();
+                let name = name.clone();
+                let location = location.clone();
+                Box::pin(async move {
+                    client
+                        .read_stream_rows(&name, offset, location.as_deref())
+                        .await
+                })
+            });
+            reader = reader.with_resume(Resume {
+                reopen,
+                offset,
+                max_attempts: self.max_resume_attempts,
+                backoff: self.backoff.clone(),
+            });
+        }
+        #[cfg(feature = "arrow")]
+        let reader = match &self.field_order {
+            Some(fields) => reader.with_field_order(fields.clone()),
+            None => reader,
+        };
+        #[cfg(feature = "arrow")]
+        let reader = match &self.known_schema {
+            Some(schema) => reader.with_known_schema(schema.clone()),
+            None => reader,
+        };
+        #[cfg(feature = "arrow")]
+        let reader = if self.strict_nonempty_streams {
+            reader.strict_nonempty()
+        } else {
+            reader
+        };
+        #[cfg(feature = "arrow")]
+        let reader = if self.strip_schema_metadata {
+            reader.strip_schema_metadata()
+        } else {
+            reader
+        };
+        Ok(reader)
+    }
+
+    /// Read all the remaining streams of this session, `concurrency` of them at a time
+    /// (at least one), into a single stream of record batches.
+    ///
+    /// By default, batches are yielded as soon as they are decoded, so the batches of
+    /// different streams are interleaved in no particular order. With `preserve_order`,
+    /// all the batches of a stream are yielded before those of the next one, in the order
+    /// [`next_stream`](ReadSession::next_stream) takes them (see
+    /// [`streams_in_order`](ReadSessionBuilder::streams_in_order)). This makes the output
+    /// deterministic at the cost of throughput and memory, since streams read ahead are
+    /// buffered until all the streams before them are done.
+    #[cfg(feature = "arrow")]
+    pub fn into_stream(
+        self,
+        concurrency: usize,
+        preserve_order: bool,
+    ) -> BoxStream<'a, Result<RecordBatch, Error>> {
+        let concurrency = concurrency.max(1);
+        let readers = stream::try_unfold(self, |mut session| async move {
+            let next = session.next_stream().await?;
+            Ok(next.map(|reader| (reader, session)))
+        });
+
+        if preserve_order {
+            readers
+                .map_ok(|reader| async move {
+                    let batches: Vec<_> = reader.into_stream()?.try_collect().await?;
+                    Ok::<_, Error>(stream::iter(batches.into_iter().map(Ok)))
+                })
+                .try_buffered(concurrency)
+                .try_flatten()
+                .boxed()
+        } else {
+            readers
+                .map(
+                    |reader| match reader.and_then(|reader| reader.into_stream()) {
+                        Ok(batches) => batches,
+                        Err(err) => stream::once(ready(Err(err))).boxed(),
+                    },
+                )
+                .flatten_unordered(concurrency)
+                .boxed()
+        }
+    }
+
+    /// Like [`into_stream`](ReadSession::into_stream) without `preserve_order`, but each
+    /// batch comes with the name of the stream it was read from, e.g. to track down a
+    /// slow stream or the origin of anomalous rows.
+    #[cfg(feature = "arrow")]
+    pub fn into_named_stream(
+        self,
+        concurrency: usize,
+    ) -> BoxStream<'a, Result<(String, RecordBatch), Error>> {
+        let concurrency = concurrency.max(1);
+        stream::try_unfold(self, |mut session| async move {
+            let next = session.next_stream().await?;
+            Ok(next.map(|reader| (reader, session)))
+        })
+        .map(|reader| {
+            let reader = match reader {
+                Ok(reader) => reader,
+                Err(err) => return stream::once(ready(Err(err))).boxed(),
+            };
+            let name = reader.stream_name().to_string();
+            match reader.into_stream() {
+                Ok(batches) => batches.map_ok(move |batch| (name.clone(), batch)).boxed(),
+                Err(err) => stream::once(ready(Err(err))).boxed(),
+            }
+        })
+        .flatten_unordered(concurrency)
+        .boxed()
+    }
+
+    /// Read all the remaining streams of this session, `concurrency` of them at a time,
+    /// into memory, e.g. for interactive analysis. All the batches are given the same
+    /// schema, which is returned along them, ready for
+    /// [`concat_batches`](arrow::compute::concat_batches): a column is nullable if it is in
+    /// any batch. Fails with [`Error::SchemaMismatch`](crate::Error::SchemaMismatch) if
+    /// batches have different columns or types.
+    ///
+    /// Batches are in the order of the streams, as with `preserve_order` in
+    /// [`into_stream`](ReadSession::into_stream).
+    #[cfg(feature = "arrow")]
+    pub async fn into_table(
+        self,
+        concurrency: usize,
+    ) -> Result<(SchemaRef, Vec<RecordBatch>), Error> {
+        let mut schema = self.arrow_schema()?;
+        if let Some(fields) = &self.field_order {
+            schema = order_columns(&RecordBatch::new_empty(schema), fields)?.schema();
+        }
+        let batches: Vec<_> = self.into_stream(concurrency, true).try_collect().await?;
+        let schema = batches
+            .first()
+            .map(|batch| batch.schema())
+            .unwrap_or(schema);
+        unify_record_batches(schema, batches)
+    }
+
+    /// Read the first `max_rows` rows of this session into a single batch, e.g. to show
+    /// them in a UI. Streams are read one after the other, and only until `max_rows` rows
+    /// are read: with a single stream (see
+    /// [`ReadSessionBuilder::preview`](ReadSessionBuilder::preview)), this downloads the
+    /// least data possible. The rest of the session is dropped.
+    #[cfg(feature = "arrow")]
+    pub async fn preview(mut self, max_rows: usize) -> Result<RecordBatch, Error> {
+        let mut schema = None;
+        let mut batches = Vec::new();
+        let mut rows = 0;
+        while rows < max_rows {
+            let reader = match self.next_stream().await? {
+                Some(reader) => reader,
+                None => break,
+            };
+            let mut stream = reader
+                .limit_rows(max_rows - rows)
+                .into_stream_with_schema()?;
+            schema.get_or_insert_with(|| stream.schema());
+            while let Some(batch) = stream.try_next().await? {
+                rows += batch.num_rows();
+                batches.push(batch);
+            }
+        }
+        let schema = match schema {
+            Some(schema) => schema,
+            None => self.arrow_schema()?,
+        };
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    /// Write all the remaining streams of this session, read `concurrency` of them at a
+    /// time as with [`into_stream`](ReadSession::into_stream), into `sink`, then
+    /// [`finish`](RecordBatchSink::finish) it. Batches are written in no particular order,
+    /// one at a time. If reading or writing fails, `sink` is
+    /// [`abort`](RecordBatchSink::abort)ed instead, and the failure is returned.
+    #[cfg(feature = "arrow")]
+    pub async fn drain_into<S: RecordBatchSink>(
+        self,
+        mut sink: S,
+        concurrency: usize,
+    ) -> Result<(), Error> {
+        let mut batches = self.into_stream(concurrency, false);
+        loop {
+            let written = match batches.try_next().await {
+                Ok(Some(batch)) => sink.write(batch).await,
+                Ok(None) => return sink.finish().await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = written {
+                // The failure of the read matters more than that of the cleanup
+                let _ = sink.abort().await;
+                return Err(err);
+            }
+        }
+    }
+
+    /// Write all the remaining streams of this session into Parquet files of at most
+    /// `rows_per_file` rows and, if set, of about `max_bytes_per_file` bytes, named after
+    /// `path_template`, see [`PartitionedParquetSink`](crate::sink::PartitionedParquetSink).
+    /// Returns the paths of the files written, each one complete with its footer.
+    ///
+    /// Streams are read one at a time, and batches are written as they are read, so that
+    /// the files are filled in a reproducible order. To read streams concurrently, give a
+    /// [`PartitionedParquetSink`](crate::sink::PartitionedParquetSink) to
+    /// [`drain_into`](ReadSession::drain_into).
+    ///
+    /// If reading or writing fails, the file being written is removed, see
+    /// [`PartitionedParquetSink::abort`](crate::sink::PartitionedParquetSink::abort). The
+    /// files completed before it are left in place, for the caller to remove or keep.
+    #[cfg(feature = "parquet")]
+    pub async fn write_parquet_partitioned(
+        self,
+        path_template: &str,
+        rows_per_file: usize,
+        max_bytes_per_file: Option<usize>,
+    ) -> Result<Vec<PathBuf>, Error> {
+        let mut sink = PartitionedParquetSink::try_new(path_template, rows_per_file, None)?;
+        if let Some(max_bytes) = max_bytes_per_file {
+            sink = sink.max_bytes_per_file(max_bytes);
+        }
+        let mut batches = self.into_stream(1, false);
+        loop {
+            let written = match batches.try_next().await {
+                Ok(Some(batch)) => sink.write(batch).await,
+                Ok(None) => return sink.close(),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = written {
+                // The failure of the read matters more than that of the cleanup
+                let _ = sink.abort();
+                return Err(err);
+            }
+        }
+    }
+
+    /// Read the remaining streams of this session, `concurrency` of them at a time as with
+    /// [`into_stream`](ReadSession::into_stream), until they are all read or `deadline`
+    /// is reached, whichever comes first. Reaching the deadline is not an error: the rows
+    /// read so far are returned, along with the offsets the unfinished streams stopped at.
+    ///
+    /// The unfinished streams are handed out again by
+    /// [`next_stream`](ReadSession::next_stream) from where they stopped, so calling this
+    /// again (e.g. on the next cycle of a latency-bound service) carries on with the read.
+    #[cfg(feature = "arrow")]
+    pub async fn drain_with_deadline(
+        &mut self,
+        deadline: Instant,
+        concurrency: usize,
+    ) -> Result<DrainProgress, Error> {
+        let mut offsets: HashMap<String, i64> = self
+            .inner
+            .streams
+            .iter()
+            .map(|stream| {
+                let offset = self.stream_offsets.get(&stream.name).copied();
+                (stream.name.clone(), offset.unwrap_or_default())
+            })
+            .collect();
+        let mut batches = Vec::new();
+
+        let progress = stream::try_unfold(&mut *self, |session| async move {
+            let next = session.next_stream().await?;
+            Ok(next.map(|reader| (reader, session)))
+        })
+        .map(|reader| {
+            let reader = match reader {
+                Ok(reader) => reader,
+                Err(err) => return stream::once(ready(Err(err))).boxed(),
+            };
+            let name = reader.stream_name().to_string();
+            let batches = match reader.into_stream() {
+                Ok(batches) => batches,
+                Err(err) => return stream::once(ready(Err(err))).boxed(),
+            };
+            // The end of the stream is marked with `None`
+            let end = stream::once(ready(Ok((name.clone(), None))));
+            batches
+                .map_ok(move |batch| (name.clone(), Some(batch)))
+                .chain(end)
+                .boxed()
+        })
+        .flatten_unordered(concurrency.max(1));
+
+        let drained = tokio::time::timeout_at(deadline.into(), async {
+            let mut progress = Box::pin(progress);
+            while let Some((name, batch)) = progress.try_next().await? {
+                match batch {
+                    Some(batch) => {
+                        *offsets.entry(name).or_default() += batch.num_rows() as i64;
+                        batches.push(batch);
+                    }
+                    None => {
+                        offsets.remove(&name);
+                    }
+                }
+            }
+            Ok::<_, Error>(())
+        })
+        .await;
+        if let Ok(Err(err)) = drained {
+            return Err(err);
+        }
+
+        for (name, offset) in &offsets {
+            if !self.inner.streams.iter().any(|stream| &stream.name == name) {
+                self.inner.streams.push(ReadStream { name: name.clone() });
+            }
+            self.stream_offsets.insert(name.clone(), *offset);
+        }
+        Ok(DrainProgress { batches, offsets })
+    }
+
+    /// The number of streams the server prepared for this session, which is at most the
+    /// requested [`max_stream_count`](ReadSessionBuilder::max_stream_count). This counts
+    /// all streams, including the ones already taken or left out by
+    /// [`sample_fraction`](ReadSessionBuilder::sample_fraction).
+    pub fn stream_count(&self) -> usize {
+        self.stream_names.len()
+    }
+
+    /// The number of streams not yet taken by [`next_stream`](ReadSession::next_stream).
+    pub fn remaining_streams(&self) -> usize {
+        self.inner.streams.len()
+    }
+
+    /// The serialized Arrow schema of this session, as an IPC message, as sent by the
+    /// server. This is `None` for Avro sessions and sessions sent without a schema. The
+    /// bytes can be kept around (e.g. as the key of a cache of deserialized schemas) and
+    /// given back to [`with_cached_schema`](ReadSession::with_cached_schema).
+    pub fn serialized_arrow_schema(&self) -> Option<&[u8]> {
+        self.arrow_schema_message().ok()
+    }
+
+    /// Decode the batches of all the streams of this session with `schema`, given that the
+    /// serialized schema of this session is `serialized_schema`, e.g. as previously
+    /// returned by [`serialized_arrow_schema`](ReadSession::serialized_arrow_schema) for
+    /// the same table. This skips the deserialization of the schema, see
+    /// [`RowsStreamReader::with_known_schema`](RowsStreamReader::with_known_schema). If the
+    /// schema of this session is different, e.g. because the table changed, `schema` is
+    /// not used.
+    #[cfg(feature = "arrow")]
+    pub fn with_cached_schema(mut self, serialized_schema: &[u8], schema: SchemaRef) -> Self {
+        if self.serialized_arrow_schema() == Some(serialized_schema) {
+            self.known_schema = Some(schema);
+        }
+        self
+    }
+
+    /// The serialized Arrow schema of this session, as an IPC message.
+    fn arrow_schema_message(&self) -> Result<&[u8], Error> {
+        match &self.inner.schema {
+            Some(Schema::ArrowSchema(ArrowSchema { serialized_schema })) => Ok(serialized_schema),
+            Some(Schema::AvroSchema(_)) => Err(Error::UnsupportedDataFormat { format: "Avro" }),
+            None => Err(Error::MissingSchema {}),
+        }
+    }
+
+    /// The Arrow schema of the rows of this session.
+    #[cfg(feature = "arrow")]
+    pub fn arrow_schema(&self) -> Result<SchemaRef, Error> {
+        if let Some(schema) = &self.known_schema {
+            return Ok(schema.clone());
+        }
+        let serialized_schema = self.arrow_schema_message()?;
+        let schema = decode_schema(strip_continuation_bytes(serialized_schema)?)?;
+        if self.strip_schema_metadata {
+            Ok(strip_schema_metadata(&schema))
+        } else {
+            Ok(schema)
+        }
+    }
+
+    /// The Avro schema of the rows of this session, as JSON. For Avro sessions, this is the
+    /// schema sent by the server. For Arrow sessions, it is derived from the Arrow schema,
+    /// using the Avro types BigQuery serializes rows with (e.g. `NUMERIC` columns are
+    /// `decimal` bytes), so that schemas look the same whatever the data format.
+    #[cfg(feature = "arrow")]
+    pub fn avro_schema_json(&self) -> Result<String, Error> {
+        match &self.inner.schema {
+            Some(Schema::AvroSchema(avro_schema)) => Ok(avro_schema.schema.clone()),
+            _ => crate::avro::avro_schema_json(&*self.arrow_schema()?),
+        }
+    }
+
+    /// The source of a Rust struct called `name`, with a field for every column of this
+    /// session, e.g. to be written to a file by a build script. The struct derives
+    /// `serde::Deserialize` from the rows of
+    /// [`RowsStreamReader::into_json_stream`](crate::read::RowsStreamReader::into_json_stream),
+    /// with `serde_json::from_value`. Nullable columns are `Option`s, and columns which are
+    /// strings in JSON (e.g. `NUMERIC`, `DATE` or `TIMESTAMP`) are `String`s.
+    ///
+    /// Only flat schemas are supported: fails if a column is a `RECORD`, see
+    /// [`RowsStreamReader::flatten`](crate::read::RowsStreamReader::flatten).
+    #[cfg(feature = "arrow")]
+    pub fn rust_struct_source(&self, name: &str) -> Result<String, Error> {
+        crate::codegen::rust_struct(name, &*self.arrow_schema()?)
+    }
+
+    /// Write the Arrow schema of this session to `w`, as an Arrow IPC stream without any
+    /// record batch. This does not read any rows: the schema is the one sent by the
+    /// server when the session was created. The output can be read back with an Arrow
+    /// [`StreamReader`](arrow::ipc::reader::StreamReader), e.g. to keep track of the
+    /// schema of a table over time.
+    pub fn write_arrow_schema<W: std::io::Write>(&self, mut w: W) -> Result<(), Error> {
+        w.write_all(self.arrow_schema_message()?)?;
+        // End of stream marker: continuation bytes and a zero length
+        w.write_all(&[255, 255, 255, 255, 0, 0, 0, 0])?;
+        Ok(())
+    }
+
+    /// The data format of the rows of this session, as set by the server. Only
+    /// [`DataFormat::Arrow`](DataFormat::Arrow) sessions can be decoded by this crate.
+    pub fn data_format(&self) -> DataFormat {
+        self.inner.data_format()
+    }
+
+    /// The snapshot time the table is read at, as reported by the server. If no
+    /// [`snapshot_time`](ReadSessionBuilder::snapshot_time) was requested, the server may
+    /// not report it, in which case this is `None`.
+    ///
+    /// Streams cannot be added to an existing session. To scale out a read, create another
+    /// session with the same snapshot time: both sessions then see the same rows. Note that
+    /// the streams of the two sessions overlap, so each session should be restricted (e.g.
+    /// with [`row_restriction`](ReadSessionBuilder::row_restriction)) to a different part
+    /// of the table.
+    pub fn snapshot_time(&self) -> Option<&Timestamp> {
+        self.inner
+            .table_modifiers
+            .as_ref()
+            .and_then(|table_modifiers| table_modifiers.snapshot_time.as_ref())
+    }
+
+    /// A summary of what this session is about to read, e.g. to log the cost of a read
+    /// before any rows are downloaded.
+    pub fn summary(&self) -> ReadSessionSummary {
+        let serialized_schema: &[u8] = match &self.inner.schema {
+            Some(Schema::ArrowSchema(ArrowSchema { serialized_schema })) => serialized_schema,
+            Some(Schema::AvroSchema(avro_schema)) => avro_schema.schema.as_bytes(),
+            None => &[],
+        };
+        ReadSessionSummary {
+            stream_count: self.stream_count(),
+            estimated_rows: self.inner.estimated_row_count.max(0) as u64,
+            estimated_bytes: self.inner.estimated_total_bytes_scanned.max(0) as u64,
+            expire_time: self.expire_time().cloned(),
+            snapshot_time: self.snapshot_time().cloned(),
+            schema_fingerprint: fnv1a(serialized_schema),
+        }
+    }
+
+    /// An estimate of the cost of reading this session, in the currency of
+    /// `price_per_tib`, the price of a TiB read with the Storage API in your region or
+    /// contract. This is based on the bytes the server estimates the session will scan
+    /// (see [`ReadSessionSummary::estimated_bytes`](ReadSessionSummary::estimated_bytes)),
+    /// so the actual cost may differ, e.g. if not all streams are read.
+    pub fn estimated_cost_usd(&self, price_per_tib: f64) -> f64 {
+        const TIB: f64 = (1u64 << 40) as f64;
+        let estimated_bytes = self.inner.estimated_total_bytes_scanned.max(0) as f64;
+        estimated_bytes / TIB * price_per_tib
+    }
+
+    /// The time after which this session, and all of its streams, can no longer be read.
+    /// Reading a stream of an expired session fails with `FAILED_PRECONDITION`, so
+    /// long-running jobs should create a new session before then.
+    pub fn expire_time(&self) -> Option<&Timestamp> {
+        self.inner.expire_time.as_ref()
+    }
+
+    /// The read options (e.g. selected fields and row restriction) of this session, as
+    /// echoed back by the server, which may have normalized those of the request. This is
+    /// `None` if the session reads all the rows and columns of the table.
+    pub fn effective_read_options(&self) -> Option<&TableReadOptions> {
+        self.inner.read_options.as_ref()
+    }
+
+    /// The number of bytes of serialized rows downloaded so far, across all the streams
+    /// of this read session.
+    pub fn bytes_downloaded(&self) -> u64 {
+        self.stats.bytes()
+    }
+
+    /// The number of bytes of serialized rows processed so far, across all the streams
+    /// of this read session. Unlike [`bytes_downloaded`](ReadSession::bytes_downloaded),
+    /// this is the size of the rows once uncompressed, as reported by the server.
+    pub fn bytes_processed(&self) -> u64 {
+        self.stats.uncompressed_bytes()
+    }
+}
+
+/// A builder for [`Client`](Client), for when the defaults of
+/// [`Client::new`](Client::new) do not fit, e.g. to point it at an emulator.
+///
+/// There is no option for the maximum size of the messages received: the version of
+/// `tonic` this crate is built on does not limit it, so large batches (e.g. of wide rows)
+/// are decoded whatever their size.
+pub struct ClientBuilder<C> {
+    auth: Option<Authenticator<C>>,
+    endpoint: Option<String>,
+    tls_config: Option<ClientTlsConfig>,
+    app_id: Option<String>,
+    interceptor: Option<Interceptor>,
+    reconnect_on_broken_connection: bool,
+}
+
+impl<C> ClientBuilder<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn new() -> Self {
+        Self {
+            auth: None,
+            endpoint: None,
+            tls_config: None,
+            app_id: None,
+            interceptor: None,
+            reconnect_on_broken_connection: false,
+        }
+    }
+
+    /// Use `auth` as a token generator. If not set, requests are sent without any
+    /// credentials, which is only useful against an emulator.
+    pub fn auth(mut self, auth: Authenticator<C>) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// The URI of the endpoint to send requests to (e.g. `http://localhost:9060`). TLS is
+    /// only used for `https` endpoints. If not set, defaults to Google's endpoint.
+    ///
+    /// Setting this disables the routing of requests to regional endpoints (see
+    /// [`ReadSessionBuilder::location`](ReadSessionBuilder::location)).
+    pub fn endpoint(mut self, endpoint: String) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// The TLS configuration of the connection, e.g. to trust a custom certificate
+    /// authority or to present a client certificate (mTLS) to an egress proxy. When set,
+    /// TLS is used whatever the endpoint. If not set, the platform's root certificates
+    /// are trusted and no client certificate is presented.
+    ///
+    /// The domain name of the configuration is overridden for regional endpoints.
+    pub fn tls_config(mut self, tls_config: ClientTlsConfig) -> Self {
+        self.tls_config = Some(tls_config);
+        self
+    }
+
+    /// An identifier of your application (e.g. `my-app/1.2.0`), appended to the
+    /// `x-goog-api-client` header sent with every request. That header always identifies
+    /// this crate and its version, which helps Google support when investigating issues.
+    pub fn app_id(mut self, app_id: String) -> Self {
+        self.app_id = Some(app_id);
+        self
+    }
+
+    /// Run `interceptor` on every request, once the crate has added its own headers
+    /// (authorization, routing and `x-goog-api-client`), e.g. to stamp a correlation id
+    /// on each call. Only the metadata of the request is passed, its message is not.
+    ///
+    /// Clones of the client share the same `interceptor`.
+    pub fn interceptor<F>(mut self, interceptor: F) -> Self
+    where
+        F: FnMut(&mut Request<()>) + Send + 'static,
+    {
+        self.interceptor = Some(Arc::new(Mutex::new(interceptor)));
+        self
+    }
+
+    /// Whether a call failing because its connection was broken (e.g. reset by a proxy or
+    /// a load balancer after being idle for a while) is sent again, once, on a new
+    /// connection. This prevents the first call after a long idle period from failing,
+    /// in long-lived services reading sporadically. Only the calls creating sessions and
+    /// opening streams are sent again: a stream broken while it is read fails as usual
+    /// (see [`ReadSessionBuilder::max_resume_attempts`](ReadSessionBuilder::max_resume_attempts)).
+    /// Defaults to `false`.
+    pub fn reconnect_on_broken_connection(mut self, reconnect: bool) -> Self {
+        self.reconnect_on_broken_connection = reconnect;
+        self
+    }
+
+    /// Build the [`Client`](Client). This connects to the endpoint.
+    pub async fn build(self) -> Result<Client<C>, Error> {
+        let channel = match &self.endpoint {
+            Some(endpoint) => {
+                Channel::from_shared(endpoint.clone()).map_err(|e| Error::InvalidOption {
+                    name: "endpoint",
+                    reason: e.to_string(),
+                })?
+            }
+            None => Channel::from_static(API_ENDPOINT),
+        };
+
+        let tls_config = match (&self.tls_config, &self.endpoint) {
+            (Some(tls_config), _) => Some(tls_config.clone()),
+            (None, Some(endpoint)) if endpoint.starts_with("https://") => {
+                Some(ClientTlsConfig::new())
+            }
+            (None, Some(_)) => None,
+            (None, None) => Some(ClientTlsConfig::new().domain_name(API_DOMAIN)),
+        };
+        let endpoint = match tls_config {
+            Some(tls_config) => channel.tls_config(tls_config)?,
+            None => channel,
+        };
+        let channel = endpoint.connect().await?;
+
+        let api_client = match &self.app_id {
+            Some(app_id) => MetadataValue::from_str(&format!("{} {}", API_CLIENT, app_id))?,
+            None => MetadataValue::from_static(API_CLIENT),
+        };
+
+        let big_query_read_client = BigQueryReadClient::new(channel);
+        Ok(Client {
+            auth: self.auth.map(Arc::new),
+            big_query_read_client,
+            endpoint: Some(endpoint),
+            reconnect_on_broken_connection: self.reconnect_on_broken_connection,
+            regional_clients: Arc::default(),
+            regional_routing: self.endpoint.is_none(),
+            tls_config: self.tls_config,
+            api_client,
+            interceptor: self.interceptor,
+            concurrency: None,
+            session_rate_limit: None,
+            column_denylist: None,
+        })
+    }
+}
+
+/// The main object of this crate.
+///
+/// Cloning a `Client` is cheap: clones share the same connection and token generator.
+pub struct Client<C> {
+    auth: Option<Arc<Authenticator<C>>>,
+    big_query_read_client: BigQueryReadClient<Channel>,
+    /// The endpoint of `big_query_read_client`, to connect to it again. This is `None` for
+    /// clients built with [`from_read_client`](Client::from_read_client).
+    endpoint: Option<Endpoint>,
+    reconnect_on_broken_connection: bool,
+    regional_clients: Arc<Mutex<HashMap<String, BigQueryReadClient<Channel>>>>,
+    regional_routing: bool,
+    tls_config: Option<ClientTlsConfig>,
+    /// The value of the `x-goog-api-client` header.
+    api_client: AsciiMetadataValue,
+    interceptor: Option<Interceptor>,
+    /// Bounds the number of concurrent calls, see [`Client::with_max_concurrency`](Client::with_max_concurrency).
+    concurrency: Option<Arc<Semaphore>>,
+    session_rate_limit: Option<Arc<Mutex<TokenBucket>>>,
+    column_denylist: Option<Arc<ColumnDenylist>>,
+}
+
+impl<C> Clone for Client<C> {
+    fn clone(&self) -> Self {
+        Self {
+            auth: self.auth.clone(),
+            big_query_read_client: self.big_query_read_client.clone(),
+            endpoint: self.endpoint.clone(),
+            reconnect_on_broken_connection: self.reconnect_on_broken_connection,
+            regional_clients: self.regional_clients.clone(),
+            regional_routing: self.regional_routing,
+            tls_config: self.tls_config.clone(),
+            api_client: self.api_client.clone(),
+            interceptor: self.interceptor.clone(),
+            concurrency: self.concurrency.clone(),
+            session_rate_limit: self.session_rate_limit.clone(),
+            column_denylist: self.column_denylist.clone(),
+        }
+    }
+}
+
+impl<C> Client<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    /// Create a new client using `auth` as a token generator.
+    pub async fn new(auth: Authenticator<C>) -> Result<Self, Error> {
+        Self::builder().auth(auth).build().await
+    }
+
+    /// Create a new [`ClientBuilder`](ClientBuilder).
+    pub fn builder() -> ClientBuilder<C> {
+        ClientBuilder::new()
+    }
+
+    /// Create a new client sending all its requests through `client`, e.g. one with
+    /// interceptors of your own. `auth` is still used to authorize the requests.
+    ///
+    /// Since the endpoint is chosen by `client`, requests are never routed to regional
+    /// endpoints (see [`ReadSessionBuilder::location`](ReadSessionBuilder::location)), and
+    /// calls are not sent again on broken connections (see
+    /// [`ClientBuilder::reconnect_on_broken_connection`](ClientBuilder::reconnect_on_broken_connection)).
+    pub fn from_read_client(auth: Authenticator<C>, client: BigQueryReadClient<Channel>) -> Self {
+        Self {
+            auth: Some(Arc::new(auth)),
+            big_query_read_client: client,
+            endpoint: None,
+            reconnect_on_broken_connection: false,
+            regional_clients: Arc::default(),
+            regional_routing: false,
+            tls_config: None,
+            api_client: MetadataValue::from_static(API_CLIENT),
+            interceptor: None,
+            concurrency: None,
+            session_rate_limit: None,
+            column_denylist: None,
+        }
+    }
+
+    /// Allow at most `n` calls to the API at a time, across this client and all of its
+    /// clones, e.g. to share a connection between many tasks without running out of
+    /// HTTP/2 streams or being throttled with `RESOURCE_EXHAUSTED`. Calls over the limit
+    /// wait for a slot to free up.
+    ///
+    /// A `ReadRows` call holds its slot until the server starts responding, not while its
+    /// rows are read: bound the number of streams read at once with the `concurrency` of
+    /// [`ReadSession::into_stream`](ReadSession::into_stream) instead.
+    pub fn with_max_concurrency(mut self, n: usize) -> Self {
+        self.concurrency = Some(Arc::new(Semaphore::new(n)));
+        self
+    }
+
+    /// Create at most `sessions_per_second` read sessions per second, across this client
+    /// and all of its clones, e.g. to stay within the quota of sessions created per
+    /// project. Bursts of up to a second worth of sessions are allowed. Over the limit,
+    /// creating a session waits or fails depending on `on_limit`.
+    ///
+    /// This covers all the calls to `CreateReadSession`, including the ones made by
+    /// [`ping`](Client::ping) or [`exclude_fields`](ReadSessionBuilder::exclude_fields).
+    /// Panics if `sessions_per_second` is not positive.
+    pub fn with_session_rate_limit(
+        mut self,
+        sessions_per_second: f64,
+        on_limit: OnRateLimit,
+    ) -> Self {
+        assert!(
+            sessions_per_second > 0.,
+            "the session rate limit must be positive"
+        );
+        let bucket = TokenBucket::new(sessions_per_second, on_limit);
+        self.session_rate_limit = Some(Arc::new(Mutex::new(bucket)));
+        self
+    }
+
+    /// Never read the top-level `columns` (e.g. columns holding PII) through this client
+    /// and its clones, whatever the [`selected_fields`](ReadSessionBuilder::selected_fields)
+    /// of a read session. Denying a `RECORD` column denies all its sub-fields. A session
+    /// which would read a denied column either leaves it out or fails, depending on
+    /// `on_denied`. Sessions which would read no column at all fail.
+    ///
+    /// Reading all the columns of a table costs an extra read session, created by
+    /// [`ReadSessionBuilder::build`](ReadSessionBuilder::build) to look up the columns of
+    /// the table, and requires the `arrow` feature. Requests sent as is with
+    /// [`execute_read_session`](Client::execute_read_session) are checked the same way,
+    /// but fail if they read all the columns.
+    pub fn with_column_denylist(mut self, columns: Vec<String>, on_denied: OnDeniedColumn) -> Self {
+        self.column_denylist = Some(Arc::new(ColumnDenylist { columns, on_denied }));
+        self
+    }
+
+    /// Wait for a slot to send a call, if the number of concurrent calls is bounded.
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.concurrency {
+            Some(semaphore) => Some(semaphore.acquire().await.expect("never closed")),
+            None => None,
+        }
+    }
+
+    /// Get the client for the endpoint serving `location`, or the global endpoint if
+    /// `location` is `None`. Regional channels are created on first use.
+    fn read_client(&self, location: Option<&str>) -> Result<BigQueryReadClient<Channel>, Error> {
+        let location = match location {
+            Some(location) if self.regional_routing => location,
+            _ => return Ok(self.big_query_read_client.clone()),
+        };
+
+        let mut regional_clients = self.regional_clients.lock().unwrap();
+        if let Some(client) = regional_clients.get(location) {
+            return Ok(client.clone());
+        }
+
+        let domain = regional_api_domain(location);
+        let tls_config = self
+            .tls_config
+            .clone()
+            .unwrap_or_default()
+            .domain_name(domain.clone());
+        let channel = Channel::from_shared(format!("https://{}", domain))
+            .map_err(|e| Error::InvalidOption {
+                name: "location",
+                reason: e.to_string(),
+            })?
+            .tls_config(tls_config)?
+            .connect_lazy()?;
+        let client = BigQueryReadClient::new(channel);
+        regional_clients.insert(location.to_string(), client.clone());
+        Ok(client)
+    }
+
+    /// Whether a call which failed with `status` is sent again on a new connection, see
+    /// [`ClientBuilder::reconnect_on_broken_connection`](ClientBuilder::reconnect_on_broken_connection).
+    fn should_reconnect(&self, status: &Status) -> bool {
+        self.reconnect_on_broken_connection && self.endpoint.is_some() && broken_connection(status)
+    }
+
+    /// Replace the connection to the endpoint serving `location`, see
+    /// [`read_client`](Client::read_client). Clones of this client keep the broken
+    /// connection, which the underlying channel re-establishes by itself for the next
+    /// calls.
+    fn reconnect(&mut self, location: Option<&str>) -> Result<(), Error> {
+        match location {
+            Some(location) if self.regional_routing => {
+                // Created again on next use
+                self.regional_clients.lock().unwrap().remove(location);
+            }
+            _ => {
+                if let Some(endpoint) = &self.endpoint {
+                    self.big_query_read_client = BigQueryReadClient::new(endpoint.connect_lazy()?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a new [`ReadSessionBuilder`](ReadSessionBuilder).
+    pub fn read_session_builder(&mut self, table: Table) -> ReadSessionBuilder<'_, C> {
+        ReadSessionBuilder::new(self, table)
+    }
+
+    /// Create a [`ReadSession`](ReadSession) reading `table` as it was at `snapshot_time`,
+    /// with the default options. This is a shorthand for
+    /// `read_session_builder(table).snapshot_time(snapshot_time).build()`.
+    ///
+    /// The API only reads snapshots: to see what changed within a window of time, read the
+    /// table at both ends and compare.
+    pub async fn read_at<T: Into<Timestamp>>(
+        &mut self,
+        table: Table,
+        snapshot_time: T,
+    ) -> Result<ReadSession<'_, C>, Error> {
+        self.read_session_builder(table)
+            .snapshot_time(snapshot_time.into())
+            .build()
+            .await
+    }
+
+    /// Read `table` at the snapshot times `before` and `after`, and compare the rows of
+    /// both snapshots by their `keys` (top-level columns) to find the rows which were
+    /// added and removed in between. Rows whose key is in both snapshots are not reported,
+    /// even if their other columns changed.
+    ///
+    /// Both snapshots are read into memory, so this is meant for small to medium tables.
+    /// Snapshots are only kept for the time travel window of the dataset (7 days by
+    /// default).
+    #[cfg(feature = "arrow")]
+    pub async fn diff_snapshots<T: Into<Timestamp>>(
+        &mut self,
+        table: &Table,
+        before: T,
+        after: T,
+        keys: &[String],
+    ) -> Result<SnapshotDiff, Error> {
+        let before = self.read_snapshot(table, before.into()).await?;
+        let after = self.read_snapshot(table, after.into()).await?;
+        let (added, removed) = crate::diff::diff_by_keys(&before, &after, keys)?;
+        Ok(SnapshotDiff { added, removed })
+    }
+
+    /// Read the whole of `table` at `snapshot_time`, into a single batch.
+    #[cfg(feature = "arrow")]
+    async fn read_snapshot(
+        &mut self,
+        table: &Table,
+        snapshot_time: Timestamp,
+    ) -> Result<RecordBatch, Error> {
+        let read_session = self.read_at(table.clone(), snapshot_time).await?;
+        let (schema, batches) = read_session.into_table(READ_TABLE_CONCURRENCY).await?;
+        Ok(concat_batches(&schema, &batches)?)
+    }
+
+    /// Send `req`, e.g. built with
+    /// [`ReadSessionBuilder::build_request`](ReadSessionBuilder::build_request), and wrap
+    /// the created session in a [`ReadSession`](ReadSession) with the default options.
+    /// The request is sent to the global endpoint.
+    pub async fn execute_read_session(
+        &mut self,
+        mut req: CreateReadSessionRequest,
+    ) -> Result<ReadSession<'_, C>, Error> {
+        if let Some(denylist) = &self.column_denylist {
+            let read_options = req
+                .read_session
+                .as_mut()
+                .and_then(|read_session| read_session.read_options.as_mut())
+                .filter(|read_options| !read_options.selected_fields.is_empty());
+            match read_options {
+                Some(read_options) => {
+                    let selected_fields = std::mem::take(&mut read_options.selected_fields);
+                    read_options.selected_fields = denylist.apply(selected_fields)?;
+                }
+                None => {
+                    return Err(Error::InvalidOption {
+                        name: "column_denylist",
+                        reason: "cannot check a request reading all the columns".to_string(),
+                    })
+                }
+            }
+        }
+        let inner = self.raw_create_read_session(req, None).await?;
+        Ok(ReadSession {
+            client: ClientHandle::Borrowed(self),
+            stream_names: inner
+                .streams
+                .iter()
+                .map(|stream| stream.name.clone())
+                .collect(),
+            inner,
+            location: None,
+            stats: Arc::new(SessionStats::new(None)),
+            field_order: None,
+            max_resume_attempts: 0,
+            strict_nonempty_streams: false,
+            #[cfg(feature = "arrow")]
+            known_schema: None,
+            strip_schema_metadata: false,
+            backoff: default_backoff(),
+            stream_offsets: HashMap::new(),
+        })
+    }
+
+    /// Create a new [`SessionGroupBuilder`](SessionGroupBuilder), to read several tables
+    /// as of the same snapshot time.
+    pub fn session_group_builder(&mut self) -> SessionGroupBuilder<'_, C> {
+        SessionGroupBuilder::new(self)
+    }
+
+    /// Check that this client can read `table`, i.e. that a token can be minted, that the
+    /// endpoint can be reached and that the credentials have access to the table. The API
+    /// has no dedicated health check, so this creates a read session with a single stream,
+    /// without reading any rows. Failures are reported as is: [`Error::Auth`](Error::Auth)
+    /// for credentials, [`Error::Transport`](Error::Transport) for network issues and
+    /// [`Error::Status`](Error::Status) (e.g. `PERMISSION_DENIED`) for anything the server
+    /// rejected.
+    pub async fn ping(&mut self, table: &Table) -> Result<(), Error> {
+        self.read_session_builder(table.clone())
+            .max_stream_count(1)
+            .build()
+            .await?;
+        Ok(())
+    }
+
+    /// Check that the credentials of this client hold the IAM permissions needed to read
+    /// `table` (`bigquery.readsessions.create` on the parent project and
+    /// `bigquery.tables.getData` on the table), e.g. before a scheduled job starts. A lack
+    /// of permission is reported as [`Error::MissingPermission`](Error::MissingPermission)
+    /// when the server names the permission, other failures (denials naming no permission
+    /// included) as with [`ping`](Client::ping).
+    ///
+    /// The API has no dry run: this creates a read session with a single stream, without
+    /// reading any rows. Sessions cannot be deleted, the session is left to expire.
+    pub async fn check_read_access(&mut self, table: &Table) -> Result<(), Error> {
+        self.ping(table).await.map_err(|err| match err {
+            Error::Status(status) => match missing_permission(&status) {
+                Some(permission) => Error::MissingPermission { permission },
+                None => Error::Status(status),
+            },
+            err => err,
+        })
+    }
+
+    /// Ask the server how many streams it would use to read `table`, which is a good
+    /// indication of how many workers can read it in parallel. This is the
+    /// [`stream_count`](ReadSession::stream_count) of a read session created with no
+    /// `max_stream_count`, letting the server decide based on the size of the table.
+    ///
+    /// This creates (and abandons) a read session, which counts against the quotas of
+    /// the project owning the table.
+    pub async fn suggest_stream_count(&mut self, table: &Table) -> Result<usize, Error> {
+        let read_session = self.read_session_builder(table.clone()).build().await?;
+        Ok(read_session.stream_count())
+    }
+    async fn new_request<D>(&self, t: D, params: &RequestParams) -> Result<Request<D>, Error> {
+        let mut req = Request::new(t);
+        let meta = req.metadata_mut();
+        if let Some(auth) = &self.auth {
+            let token = auth.token(&[API_SCOPE]).await?;
+            let bearer_token = format!("Bearer {}", token.as_str());
+            let bearer_value = MetadataValue::from_str(&bearer_token)?;
+            meta.insert("authorization", bearer_value);
+        }
+        let params = params.to_string();
+        meta.insert("x-goog-request-params", MetadataValue::from_str(&params)?);
+        meta.insert("x-goog-api-client", self.api_client.clone());
+        if let Some(interceptor) = &self.interceptor {
+            let mut bare = Request::new(());
+            std::mem::swap(bare.metadata_mut(), req.metadata_mut());
+            (interceptor.lock().unwrap())(&mut bare);
+            std::mem::swap(bare.metadata_mut(), req.metadata_mut());
+        }
+        Ok(req)
+    }
+
+    /// Send `req` to the `CreateReadSession` endpoint as is, along with the authorization
+    /// and routing headers. If set, `location` selects the regional endpoint to send the
+    /// request to, as with [`ReadSessionBuilder::location`](ReadSessionBuilder::location).
+    ///
+    /// This is a low-level building block: prefer
+    /// [`read_session_builder`](Client::read_session_builder) unless you need to control
+    /// the request entirely.
+    pub async fn raw_create_read_session(
+        &mut self,
+        req: CreateReadSessionRequest,
+        location: Option<&str>,
+    ) -> Result<BigQueryReadSession, Error> {
+        if let Some(bucket) = &self.session_rate_limit {
+            let wait = bucket.lock().unwrap().take()?;
+            if wait > Duration::from_secs(0) {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
+        let params = RequestParams::for_create_read_session(&req);
+        let mut reconnected = false;
+        loop {
+            let wrapped = self.new_request(req.clone(), &params).await?;
+            let created = {
+                let _permit = self.acquire().await;
+                self.read_client(location)?
+                    .create_read_session(wrapped)
+                    .await
+            };
+            match created {
+                Err(status) if !reconnected && self.should_reconnect(&status) => {
+                    reconnected = true;
+                    self.reconnect(location)?;
+                }
+                created => return Ok(created?.into_inner()),
+            }
+        }
+    }
+
+    /// Send `req` to the `ReadRows` endpoint as is, along with the authorization and
+    /// routing headers. If set, `location` selects the regional endpoint to send the
+    /// request to, which should be the one the session was created with.
+    ///
+    /// This is a low-level building block: prefer
+    /// [`ReadSession::next_stream`](ReadSession::next_stream) unless you need to control
+    /// the request entirely.
+    pub async fn raw_read_rows(
+        &mut self,
+        req: ReadRowsRequest,
+        location: Option<&str>,
+    ) -> Result<Streaming<ReadRowsResponse>, Error> {
+        let params = RequestParams::for_read_rows(&req);
+        let mut reconnected = false;
+        loop {
+            let wrapped = self.new_request(req.clone(), &params).await?;
+            let opened = {
+                let _permit = self.acquire().await;
+                self.read_client(location)?.read_rows(wrapped).await
+            };
+            match opened {
+                Err(status) if !reconnected && self.should_reconnect(&status) => {
+                    reconnected = true;
+                    self.reconnect(location)?;
+                }
+                opened => return Ok(opened?.into_inner()),
+            }
+        }
+    }
+
+    async fn read_stream_rows(
+        &mut self,
+        stream: &str,
+        offset: i64,
+        location: Option<&str>,
+    ) -> Result<Streaming<ReadRowsResponse>, Error> {
+        let req = ReadRowsRequest {
+            read_stream: stream.to_string(),
+            offset,
         };
-        let params = format!("read_stream={}", req.read_stream);
-        let wrapped = self.new_request(req, &params).await?;
-        let read_rows_response = self
-            .big_query_read_client
-            .read_rows(wrapped)
-            .await?
-            .into_inner();
-        Ok(read_rows_response)
+        self.raw_read_rows(req, location).await
     }
 }
 
+/// How many streams [`read_table`](read_table) reads at a time.
+#[cfg(feature = "arrow")]
+const READ_TABLE_CONCURRENCY: usize = 8;
+
+/// Read the whole of `table` into memory, in one call: this creates a
+/// [`Client`](Client) with `auth`, and a [`ReadSession`](ReadSession) owned by the
+/// project `parent_project_id`, which is then read with
+/// [`into_table`](ReadSession::into_table), a few streams at a time.
+///
+/// This is meant for small tables and quick scripts. To select columns, filter rows or
+/// stream large tables, use a [`ReadSessionBuilder`](ReadSessionBuilder).
+#[cfg(feature = "arrow")]
+pub async fn read_table<C>(
+    auth: Authenticator<C>,
+    table: Table,
+    parent_project_id: &str,
+) -> Result<Vec<RecordBatch>, Error>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut client = Client::new(auth).await?;
+    let read_session = client
+        .read_session_builder(table)
+        .parent_project_id(parent_project_id.to_string())
+        .build()
+        .await?;
+    let (_, batches) = read_session.into_table(READ_TABLE_CONCURRENCY).await?;
+    Ok(batches)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use futures::stream::TryStreamExt;
+    use hyper::client::HttpConnector;
+
+    use crate::mock::{test_batch, test_schema, MockBigQueryRead};
+
+    fn test_table() -> Table {
+        Table::new("bigquery-public-data", "london_bicycles", "cycle_stations")
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_arrow_reader() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .parent_project_id("openquery-public-testing".to_string())
+            .build()
+            .await
+            .unwrap();
+
+        let mut num_rows = 0;
+        let mut num_streams = 0;
+        assert_eq!(read_session.remaining_streams(), 2);
+        while let Some(stream_reader) = read_session.next_stream().await.unwrap() {
+            num_streams += 1;
+            assert_eq!(read_session.remaining_streams(), 2 - num_streams);
+            let arrow_stream_reader = stream_reader.into_arrow_reader().await.unwrap();
+            for record_batch in arrow_stream_reader {
+                num_rows += record_batch.unwrap().num_rows();
+            }
+        }
+
+        assert_eq!(num_streams, 2);
+        assert_eq!(num_rows, 12);
+    }
+
+    #[tokio::test]
+    async fn mock_arrow_reader_completed_normally() {
+        let mock = MockBigQueryRead {
+            end_after: Some(1),
+            ..Default::default()
+        };
+        let mut client = mock.client().await;
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let arrow_stream_reader = stream_reader.into_checked_arrow_reader().await.unwrap();
+        assert!(!arrow_stream_reader.completed_normally());
+        assert_eq!(
+            arrow_stream_reader
+                .map(|batch| batch.unwrap().num_rows())
+                .sum::<usize>(),
+            3
+        );
+
+        // The progress computed by the server may end slightly below 1.0
+        let mock = MockBigQueryRead {
+            final_progress: Some(1. - f64::EPSILON * 4.),
+            ..Default::default()
+        };
+        let mut client = mock.client().await;
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let arrow_stream_reader = stream_reader.into_checked_arrow_reader().await.unwrap();
+        assert!(arrow_stream_reader.completed_normally());
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_stream() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_peek_schema() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        assert_eq!(stream_reader.peek_schema().await.unwrap(), test_schema());
+
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_stream_with_schema() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream = read_session
+            .next_stream()
+            .await
+            .unwrap()
+            .unwrap()
+            .with_field_order(vec!["name".to_string(), "id".to_string()])
+            .into_stream_with_schema()
+            .unwrap();
+        let schema = stream.schema();
+        let names: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect();
+        assert_eq!(names, vec!["name", "id"]);
+
+        let batches: Vec<_> = stream.try_collect().await.unwrap();
+        assert!(batches.iter().all(|batch| batch.schema() == schema));
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_schema_in_rows() {
+        let mut client = MockBigQueryRead {
+            schema_in_rows: true,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_zstd_buffers() {
+        let mut client = MockBigQueryRead {
+            buffer_compression: Some(arrow::ipc::CompressionType::ZSTD),
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .arrow_buffer_compression(CompressionCodec::Zstd)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_lz4_rows() {
+        let mut client = MockBigQueryRead {
+            lz4_rows: true,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .response_compression_codec(ResponseCompressionCodec::Lz4)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_tee() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let mut streams = stream_reader.tee(2, 1).unwrap();
+        let second = streams.pop().unwrap();
+        let first = streams.pop().unwrap();
+        let (first, second): (Result<Vec<_>, _>, Result<Vec<_>, _>) =
+            futures::join!(first.try_collect(), second.try_collect());
+
+        let expected = vec![test_batch(0, 3), test_batch(3, 3)];
+        assert_eq!(first.unwrap(), expected);
+        assert_eq!(second.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_tee_shared() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let mut streams = stream_reader.tee_shared(2, 1).unwrap();
+        let second = streams.pop().unwrap();
+        let first = streams.pop().unwrap();
+        let (first, second): (Result<Vec<_>, _>, Result<Vec<_>, _>) =
+            futures::join!(first.try_collect(), second.try_collect());
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(first.len(), 2);
+        assert_eq!(*first[0], test_batch(0, 3));
+        assert!(first.iter().zip(&second).all(|(a, b)| Arc::ptr_eq(a, b)));
+    }
+
+    #[tokio::test]
+    async fn mock_dropped_readers_cancel_streams() {
+        use std::sync::atomic::Ordering;
+
+        let mock = MockBigQueryRead {
+            hang_after_rows: true,
+            ..Default::default()
+        };
+        let open_streams = mock.open_streams.clone();
+        let mut client = mock.client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        let name = read_session.inner.streams[0].name.clone();
+
+        for i in 0..50 {
+            let reader = read_session.stream_at(&name, 0).await.unwrap();
+            let mut batches = if i % 2 == 0 {
+                reader.into_stream().unwrap()
+            } else {
+                reader.into_buffered_stream(1).unwrap()
+            };
+            assert_eq!(batches.next().await.unwrap().unwrap(), test_batch(0, 3));
+        }
+
+        for _ in 0..100 {
+            if open_streams.load(Ordering::SeqCst) == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(open_streams.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_strip_schema_metadata() {
+        use arrow::datatypes::{Field, Schema};
+
+        let metadata: HashMap<String, String> =
+            vec![("description".to_string(), "a column".to_string())]
+                .into_iter()
+                .collect();
+        let fields: Vec<Field> = test_schema()
+            .fields()
+            .iter()
+            .map(|field| field.as_ref().clone().with_metadata(metadata.clone()))
+            .collect();
+        let mock = MockBigQueryRead {
+            schema: Arc::new(Schema::new_with_metadata(fields, metadata)),
+            ..Default::default()
+        };
+        let mut client = mock.client().await;
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .strip_schema_metadata(true)
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(read_session.arrow_schema().unwrap(), test_schema());
+
+        let batches: Vec<_> = read_session
+            .into_stream(1, true)
+            .try_collect()
+            .await
+            .unwrap();
+        let stream_batches = vec![test_batch(0, 3), test_batch(3, 3)];
+        assert_eq!(batches, [stream_batches.clone(), stream_batches].concat());
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_filter_rows() {
+        use arrow::array::{AsArray, BooleanArray};
+        use arrow::datatypes::Int64Type;
+
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .filter_rows(|batch| {
+                let ids = batch.column(0).as_primitive::<Int64Type>();
+                ids.iter()
+                    .map(|id| id.map(|id| id % 2 == 0))
+                    .collect::<BooleanArray>()
+            })
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let batch = arrow::compute::concat_batches(&test_schema(), &batches).unwrap();
+        let expected: Vec<_> = (0..6).filter(|id| id % 2 == 0).collect();
+        let ids: Vec<_> = batch
+            .column(0)
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_stream_window() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let name = format!("{}/sessions/mock/streams/0", test_table());
+        let batches: Vec<_> = read_session
+            .stream_window(&name, 3, 2)
+            .await
+            .unwrap()
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(3, 2)]);
+        assert_eq!(read_session.remaining_streams(), 1);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_max_batch_rows() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .max_batch_rows(2)
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(
+            batches,
+            vec![
+                test_batch(0, 2),
+                test_batch(2, 1),
+                test_batch(3, 2),
+                test_batch(5, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_coalesce_batches() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .coalesce_batches(4)
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 6)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_resume_aborted_stream() {
+        let mut client = MockBigQueryRead {
+            abort_after: Some(1),
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .max_resume_attempts(1)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![test_batch(0, 3), test_batch(3, 3)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_failed_precondition_not_resumed() {
+        let mut client = MockBigQueryRead {
+            abort_after: Some(1),
+            abort_code: Code::FailedPrecondition,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .max_resume_attempts(3)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Result<Vec<_>, _> = stream_reader.into_stream().unwrap().try_collect().await;
+
+        assert!(matches!(
+            batches,
+            Err(Error::Status(status)) if status.code() == Code::FailedPrecondition
+        ));
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_backoff_gives_up() {
+        use crate::backoff::Backoff;
+
+        struct GiveUp;
+
+        impl Backoff for GiveUp {
+            fn next_delay(&mut self, _attempt: u32) -> Option<Duration> {
+                None
+            }
+        }
+
+        let mut client = MockBigQueryRead {
+            abort_after: Some(1),
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .max_resume_attempts(1)
+            .backoff(Arc::new(Mutex::new(GiveUp)))
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Result<Vec<_>, _> = stream_reader.into_stream().unwrap().try_collect().await;
+
+        assert!(matches!(batches, Err(Error::Status(status)) if status.code() == Code::Aborted));
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_next_indexed_stream() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut indices = Vec::new();
+        while let Some((index, reader)) = read_session.next_indexed_stream().await.unwrap() {
+            assert!(reader
+                .stream_name()
+                .ends_with(&format!("/streams/{}", index)));
+            indices.push(index);
+        }
+        assert_eq!(indices, vec![1, 0]);
+        assert_eq!(read_session.stream_index("unknown"), None);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_drain_with_deadline() {
+        let mut client = MockBigQueryRead {
+            hang_after_rows: true,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_millis(200);
+        let progress = read_session.drain_with_deadline(deadline, 2).await.unwrap();
+        assert!(!progress.is_complete());
+        assert_eq!(progress.batches.len(), 4);
+        assert_eq!(progress.offsets.len(), 2);
+        assert!(progress.offsets.values().all(|offset| *offset == 6));
+        assert_eq!(read_session.remaining_streams(), 2);
+
+        // Streams are resumed where they stopped
+        let deadline = Instant::now() + Duration::from_millis(200);
+        let next = read_session.drain_with_deadline(deadline, 2).await.unwrap();
+        assert!(next.batches.is_empty());
+        assert_eq!(next.offsets, progress.offsets);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_preview() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let preview = client
+            .read_session_builder(test_table())
+            .preview(4)
+            .await
+            .unwrap();
+        assert_eq!(preview, test_batch(0, 4));
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        let preview = read_session.preview(8).await.unwrap();
+        assert_eq!(preview.num_rows(), 8);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_exclude_fields() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .exclude_fields(vec!["name".to_string()])
+            .build()
+            .await
+            .unwrap();
+
+        let read_options = read_session.inner.read_options.as_ref().unwrap();
+        assert_eq!(read_options.selected_fields, vec!["id".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn mock_ping() {
+        let mut client = MockBigQueryRead::default().client().await;
+        client.ping(&test_table()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_into_named_stream() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut batches: Vec<_> = read_session
+            .into_named_stream(2)
+            .try_collect()
+            .await
+            .unwrap();
+        batches.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let names: Vec<_> = batches.iter().map(|(name, _)| name.as_str()).collect();
+        let stream = |i| format!("{}/sessions/mock/streams/{}", test_table(), i);
+        assert_eq!(names, vec![stream(0), stream(0), stream(1), stream(1)]);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_into_table() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let (schema, batches) = read_session.into_table(2).await.unwrap();
+        assert_eq!(schema, test_schema());
+        assert_eq!(batches.len(), 4);
+    }
+
+    #[test]
+    fn unify_nullability() {
+        use arrow::datatypes::{DataType, Field, Schema};
+
+        let nullable_schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let nullable = test_batch(3, 3)
+            .with_schema(nullable_schema.clone())
+            .unwrap();
+        let (schema, batches) =
+            unify_record_batches(test_schema(), vec![test_batch(0, 3), nullable]).unwrap();
+        assert_eq!(schema, nullable_schema);
+        assert!(batches
+            .iter()
+            .all(|batch| batch.schema() == nullable_schema));
+
+        let renamed = Arc::new(Schema::new(vec![
+            Field::new("key", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let renamed = RecordBatch::try_new(renamed, test_batch(0, 3).columns().to_vec()).unwrap();
+        assert!(matches!(
+            unify_record_batches(test_schema(), vec![renamed]),
+            Err(Error::SchemaMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn mock_drain_into() {
+        struct CountingSink<'a> {
+            rows: usize,
+            total: &'a mut usize,
+        }
+
+        #[tonic::async_trait]
+        impl<'a> RecordBatchSink for CountingSink<'a> {
+            async fn write(&mut self, batch: RecordBatch) -> Result<(), Error> {
+                self.rows += batch.num_rows();
+                Ok(())
+            }
+
+            async fn finish(self) -> Result<(), Error> {
+                *self.total = self.rows;
+                Ok(())
+            }
+        }
+
+        let mut client = MockBigQueryRead::default().client().await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut total = 0;
+        let sink = CountingSink {
+            rows: 0,
+            total: &mut total,
+        };
+        read_session.drain_into(sink, 2).await.unwrap();
+        assert_eq!(total, 12);
+    }
+
+    #[tokio::test]
+    async fn mock_drain_into_aborts_on_failure() {
+        struct RecordingSink<'a> {
+            outcome: &'a mut Option<&'static str>,
+        }
+
+        #[tonic::async_trait]
+        impl<'a> RecordBatchSink for RecordingSink<'a> {
+            async fn write(&mut self, _batch: RecordBatch) -> Result<(), Error> {
+                Ok(())
+            }
+
+            async fn finish(self) -> Result<(), Error> {
+                *self.outcome = Some("finished");
+                Ok(())
+            }
+
+            async fn abort(self) -> Result<(), Error> {
+                *self.outcome = Some("aborted");
+                Ok(())
+            }
+        }
+
+        let mut client = MockBigQueryRead {
+            abort_after: Some(1),
+            ..Default::default()
+        }
+        .client()
+        .await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut outcome = None;
+        let sink = RecordingSink {
+            outcome: &mut outcome,
+        };
+        let drained = read_session.drain_into(sink, 1).await;
+        assert!(matches!(
+            drained,
+            Err(Error::Status(status)) if status.code() == Code::Aborted
+        ));
+        assert_eq!(outcome, Some("aborted"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[tokio::test]
+    async fn mock_write_parquet_partitioned() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut client = MockBigQueryRead::default().client().await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("bq-partitioned-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let template = dir.join("part-{}.parquet");
+        let paths = read_session
+            .write_parquet_partitioned(template.to_str().unwrap(), 5, None)
+            .await
+            .unwrap();
+
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].ends_with("part-0000.parquet"));
+        let rows: Vec<usize> = paths
+            .iter()
+            .map(|path| {
+                let file = std::fs::File::open(path).unwrap();
+                let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+                reader.metadata().file_metadata().num_rows() as usize
+            })
+            .collect();
+        assert_eq!(rows, vec![5, 5, 2]);
+
+        // A file per batch, every batch being over one byte
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        let template = dir.join("small-{}.parquet");
+        let paths = read_session
+            .write_parquet_partitioned(template.to_str().unwrap(), 5, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 4);
+
+        let template = dir.join("aborted-{}.parquet");
+        let mut sink =
+            PartitionedParquetSink::try_new(template.to_str().unwrap(), 5, None).unwrap();
+        sink.write(test_batch(0, 3)).await.unwrap();
+        let partial = sink.paths()[0].clone();
+        assert!(partial.exists());
+        assert!(sink.abort().unwrap().is_empty());
+        assert!(!partial.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn mock_strict_nonempty_streams() {
+        let mut client = MockBigQueryRead {
+            batches: vec![],
+            stream_count: 1,
+            ..Default::default()
+        }
+        .client()
+        .await;
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .strict_nonempty_streams(true)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let stream_name = stream_reader.stream_name().to_string();
+        let err = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::EmptyStream { stream } if stream == stream_name));
+    }
+
+    #[tokio::test]
+    async fn mock_max_concurrency() {
+        let client = MockBigQueryRead::default()
+            .client()
+            .await
+            .with_max_concurrency(1);
+        let (mut first, mut second) = (client.clone(), client.clone());
+        let table = test_table();
+        let (first, second) = tokio::join!(first.ping(&table), second.ping(&table));
+        first.unwrap();
+        second.unwrap();
+    }
+
+    #[tokio::test]
+    async fn mock_interceptor() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let endpoint = MockBigQueryRead::default().serve().await;
+        let mut client: Client<HttpConnector> = Client::builder()
+            .endpoint(endpoint)
+            .interceptor({
+                let seen = seen.clone();
+                move |req: &mut Request<()>| {
+                    let params = req.metadata().get("x-goog-request-params").cloned();
+                    seen.lock().unwrap().push(params);
+                }
+            })
+            .build()
+            .await
+            .unwrap();
+        client.ping(&test_table()).await.unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert!(!seen.is_empty());
+        assert!(seen.iter().all(Option::is_some));
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_decimals() {
+        use arrow::array::{Decimal128Array, Decimal256Array};
+        use arrow::datatypes::{i256, DataType, Field, Schema};
+        use arrow::record_batch::RecordBatch;
+
+        // NUMERIC and BIGNUMERIC, as serialized by the API
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("numeric", DataType::Decimal128(38, 9), true),
+            Field::new("bignumeric", DataType::Decimal256(76, 38), true),
+        ]));
+        let numeric = Decimal128Array::from(vec![Some(123_450_000_000), None])
+            .with_precision_and_scale(38, 9)
+            .unwrap();
+        let bignumeric = Decimal256Array::from(vec![None, Some(i256::from_i128(-1))])
+            .with_precision_and_scale(76, 38)
+            .unwrap();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(numeric), Arc::new(bignumeric)],
+        )
+        .unwrap();
+
+        let mut client = MockBigQueryRead {
+            schema: schema.clone(),
+            batches: vec![batch.clone()],
+            stream_count: 1,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![batch]);
+        assert_eq!(batches[0].schema(), schema);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_all_types() {
+        use arrow::array::{
+            ArrayRef, BinaryArray, BooleanArray, Date32Array, Float64Array, Int64Array, ListArray,
+            StringArray, StructArray, Time64MicrosecondArray, TimestampMicrosecondArray,
+        };
+        use arrow::datatypes::{DataType, Field, Fields, Int64Type, Schema, TimeUnit};
+        use arrow::record_batch::RecordBatch;
+
+        // One column of each BigQuery type, as serialized by the API
+        let address_fields = Fields::from(vec![Field::new("city", DataType::Utf8, true)]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("int64", DataType::Int64, true),
+            Field::new("float64", DataType::Float64, true),
+            Field::new("bool", DataType::Boolean, true),
+            Field::new("string", DataType::Utf8, true),
+            Field::new("geography", DataType::Utf8, true),
+            Field::new("json", DataType::Utf8, true),
+            Field::new("bytes", DataType::Binary, true),
+            Field::new("date", DataType::Date32, true),
+            Field::new("time", DataType::Time64(TimeUnit::Microsecond), true),
+            Field::new(
+                "datetime",
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+                true,
+            ),
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                true,
+            ),
+            Field::new(
+                "array",
+                DataType::List(Arc::new(Field::new("item", DataType::Int64, true))),
+                false,
+            ),
+            Field::new("struct", DataType::Struct(address_fields.clone()), true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(vec![Some(i64::MIN), None])),
+            Arc::new(Float64Array::from(vec![Some(f64::INFINITY), None])),
+            Arc::new(BooleanArray::from(vec![Some(true), None])),
+            Arc::new(StringArray::from(vec![Some("héllo"), None])),
+            Arc::new(StringArray::from(vec![Some("POINT(1 2)"), None])),
+            Arc::new(StringArray::from(vec![Some(r#"{"a":1}"#), None])),
+            Arc::new(BinaryArray::from(vec![Some(&[0u8, 255][..]), None])),
+            Arc::new(Date32Array::from(vec![Some(19_000), None])),
+            Arc::new(Time64MicrosecondArray::from(vec![
+                Some(86_399_999_999),
+                None,
+            ])),
+            Arc::new(TimestampMicrosecondArray::from(vec![
+                Some(1_600_000_000_000_001),
+                None,
+            ])),
+            Arc::new(
+                TimestampMicrosecondArray::from(vec![Some(1_600_000_000_000_001), None])
+                    .with_timezone("UTC"),
+            ),
+            Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![]),
+            ])),
+            Arc::new(StructArray::new(
+                address_fields,
+                vec![Arc::new(StringArray::from(vec![Some("London"), None])) as ArrayRef],
+                None,
+            )),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+
+        let mut client = MockBigQueryRead {
+            schema: schema.clone(),
+            batches: vec![batch.clone()],
+            stream_count: 1,
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(read_session.arrow_schema().unwrap(), schema);
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        assert_eq!(batches, vec![batch]);
+        assert_eq!(batches[0].schema(), schema);
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_into_ordered_stream() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .streams_in_order(true)
+            .build()
+            .await
+            .unwrap();
+
+        let batches: Vec<_> = read_session
+            .into_stream(2, true)
+            .try_collect()
+            .await
+            .unwrap();
+
+        let stream_batches = vec![test_batch(0, 3), test_batch(3, 3)];
+        assert_eq!(batches, [stream_batches.clone(), stream_batches].concat());
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_write_arrow_schema() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+
+        let mut buf = Vec::new();
+        read_session.write_arrow_schema(&mut buf).unwrap();
+        let reader =
+            arrow::ipc::reader::StreamReader::try_new(std::io::Cursor::new(buf), None).unwrap();
+
+        assert_eq!(reader.schema(), test_schema());
+        assert_eq!(read_session.arrow_schema().unwrap(), test_schema());
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_with_cached_schema() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        let serialized_schema = read_session.serialized_arrow_schema().unwrap().to_vec();
+        let schema = read_session.arrow_schema().unwrap();
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap()
+            .with_cached_schema(&serialized_schema, schema.clone());
+        assert!(Arc::ptr_eq(&read_session.arrow_schema().unwrap(), &schema));
+
+        let batches: Vec<_> = read_session
+            .into_stream(1, true)
+            .try_collect()
+            .await
+            .unwrap();
+        let stream_batches = vec![test_batch(0, 3), test_batch(3, 3)];
+        assert_eq!(batches, [stream_batches.clone(), stream_batches].concat());
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_preserve_field_order() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let mut read_session = client
+            .read_session_builder(test_table())
+            .selected_fields(vec!["name".to_string(), "id".to_string()])
+            .preserve_field_order(true)
+            .build()
+            .await
+            .unwrap();
+
+        let stream_reader = read_session.next_stream().await.unwrap().unwrap();
+        let batches: Vec<_> = stream_reader
+            .into_stream()
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+        let expected = test_batch(0, 3).project(&[1, 0]).unwrap();
+        assert_eq!(batches[0], expected);
+    }
+
+    #[tokio::test]
+    async fn mock_session_group() {
+        let mut client = MockBigQueryRead::default().client().await;
+
+        let other_table = Table::new("bigquery-public-data", "london_bicycles", "cycle_hire");
+        let sessions = client
+            .session_group_builder()
+            .table(test_table())
+            .table(other_table.clone())
+            .build()
+            .await
+            .unwrap();
+
+        assert_eq!(sessions.len(), 2);
+        let snapshot_time = |table: &Table| {
+            sessions[table]
+                .inner
+                .table_modifiers
+                .as_ref()
+                .and_then(|table_modifiers| table_modifiers.snapshot_time.clone())
+        };
+        assert!(snapshot_time(&test_table()).is_some());
+        assert_eq!(snapshot_time(&test_table()), snapshot_time(&other_table));
+    }
+
+    #[test]
+    fn create_read_session_request_params() {
+        let table = Table::new("bigquery-public-data", "london_bicycles", "cycle_stations");
+        let req = CreateReadSessionRequest {
+            parent: "projects/openquery-public-testing".to_string(),
+            read_session: Some(BigQueryReadSession {
+                table: table.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            RequestParams::for_create_read_session(&req).to_string(),
+            "read_session.table=projects/bigquery-public-data/datasets/london_bicycles/tables/cycle_stations"
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_build_request() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let req = client
+            .read_session_builder(test_table())
+            .parent_project_id("billing".to_string())
+            .selected_fields(vec!["id".to_string()])
+            .build_request()
+            .unwrap();
+
+        assert_eq!(req.parent, "projects/billing");
+        let read_session = req.read_session.as_ref().unwrap();
+        assert_eq!(read_session.table, test_table().to_string());
+        assert_eq!(
+            read_session.read_options.as_ref().unwrap().selected_fields,
+            vec!["id"]
+        );
+
+        let read_session = client.execute_read_session(req).await.unwrap();
+        assert_eq!(read_session.stream_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn mock_build_request_sample_percentage() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let req = client
+            .read_session_builder(test_table())
+            .sample_percentage(12.5)
+            .build_request()
+            .unwrap();
+
+        let read_options = req.read_session.unwrap().read_options.unwrap();
+        assert_eq!(read_options.sample_percentage, Some(12.5));
+    }
+
+    #[tokio::test]
+    async fn mock_build_request_arrow_buffer_compression() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let req = client
+            .read_session_builder(test_table())
+            .arrow_buffer_compression(CompressionCodec::Lz4Frame)
+            .build_request()
+            .unwrap();
+
+        let read_options = req.read_session.unwrap().read_options.unwrap();
+        assert_eq!(
+            read_options.output_format_serialization_options,
+            Some(OutputFormatSerializationOptions::ArrowSerializationOptions(
+                ArrowSerializationOptions {
+                    buffer_compression: CompressionCodec::Lz4Frame as i32,
+                }
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_read_session_effective_read_options() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let read_session = client
+            .read_session_builder(test_table())
+            .selected_fields(vec!["id".to_string()])
+            .row_restriction("id > 1".to_string())
+            .build()
+            .await
+            .unwrap();
+        let read_options = read_session.effective_read_options().unwrap();
+        assert_eq!(read_options.selected_fields, vec!["id"]);
+        assert_eq!(read_options.row_restriction, "id > 1");
+
+        let read_session = client
+            .read_session_builder(test_table())
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(read_session.effective_read_options(), None);
+    }
+
+    #[tokio::test]
+    async fn mock_build_request_with_raw_session() {
+        let mut client = MockBigQueryRead::default().client().await;
+        let raw_session = BigQueryReadSession {
+            table: "ignored".to_string(),
+            read_options: Some(TableReadOptions {
+                row_restriction: "id > 1".to_string(),
+                selected_fields: vec!["name".to_string()],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let req = client
+            .read_session_builder(test_table())
+            .with_raw_session(raw_session)
+            .selected_fields(vec!["id".to_string()])
+            .build_request()
+            .unwrap();
+
+        let read_session = req.read_session.unwrap();
+        assert_eq!(read_session.table, test_table().to_string());
+        assert_eq!(read_session.data_format(), DataFormat::Arrow);
+        let read_options = read_session.read_options.unwrap();
+        assert_eq!(read_options.selected_fields, vec!["id"]);
+        assert_eq!(read_options.row_restriction, "id > 1");
+    }
+
+    #[test]
+    fn column_denylist() {
+        let fields = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let denylist = ColumnDenylist {
+            columns: fields(&["email", "address"]),
+            on_denied: OnDeniedColumn::Drop,
+        };
+        assert_eq!(
+            denylist
+                .apply(fields(&["id", "email", "address.city"]))
+                .unwrap(),
+            vec!["id"]
+        );
+        assert!(matches!(
+            denylist.apply(fields(&["email"])),
+            Err(Error::ForbiddenColumn { column }) if column == "email"
+        ));
+
+        let denylist = ColumnDenylist {
+            on_denied: OnDeniedColumn::Fail,
+            ..denylist
+        };
+        assert_eq!(
+            denylist.apply(fields(&["id", "emails"])).unwrap(),
+            vec!["id", "emails"]
+        );
+        assert!(matches!(
+            denylist.apply(fields(&["id", "address.city"])),
+            Err(Error::ForbiddenColumn { column }) if column == "address.city"
+        ));
+    }
+
+    #[test]
+    fn parent_project_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert_eq!(project_from_env(env(&[])), None);
+        assert_eq!(
+            project_from_env(env(&[
+                ("GCLOUD_PROJECT", "b"),
+                ("GOOGLE_CLOUD_PROJECT", "a")
+            ])),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            project_from_env(env(&[
+                ("GCLOUD_PROJECT", "b"),
+                ("GOOGLE_CLOUD_PROJECT", "")
+            ])),
+            Some("b".to_string())
+        );
+    }
+
+    #[test]
+    fn streams_are_send_and_unpin() {
+        use crate::read::{
+            RecordBatchStream, RecordBatchStreamWithSchema, SharedRecordBatchStream,
+        };
+
+        fn assert_send_unpin<T: Send + Unpin + 'static>() {}
+        fn assert_send<T: Send + 'static>() {}
+
+        assert_send_unpin::<RecordBatchStream>();
+        assert_send_unpin::<SharedRecordBatchStream>();
+        assert_send_unpin::<RecordBatchStreamWithSchema>();
+        assert_send_unpin::<BoxStream<'static, Result<(String, RecordBatch), Error>>>();
+        assert_send::<RowsStreamReader>();
+        assert_send::<OwnedReadSession<hyper::client::HttpConnector>>();
+    }
+
+    #[test]
+    fn session_rate_limit() {
+        let mut bucket = TokenBucket::new(2., OnRateLimit::Fail);
+        assert!(bucket.take().is_ok());
+        assert!(bucket.take().is_ok());
+        assert!(matches!(
+            bucket.take(),
+            Err(Error::SessionRateLimited { .. })
+        ));
+
+        let mut bucket = TokenBucket::new(2., OnRateLimit::Wait);
+        assert_eq!(bucket.take().unwrap(), Duration::from_secs(0));
+        assert_eq!(bucket.take().unwrap(), Duration::from_secs(0));
+        assert!(bucket.take().unwrap() > Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn mock_from_read_client() {
+        let endpoint = MockBigQueryRead::default().serve().await;
+        let channel = Channel::from_shared(endpoint)
+            .unwrap()
+            .connect_lazy()
+            .unwrap();
+        let auth = yup_oauth2::InstalledFlowAuthenticator::builder(
+            yup_oauth2::ApplicationSecret::default(),
+            yup_oauth2::InstalledFlowReturnMethod::HTTPRedirect,
+        )
+        .build()
+        .await
+        .unwrap();
+        let mut client = Client::from_read_client(auth, BigQueryReadClient::new(channel));
+
+        // There is no endpoint to connect to again
+        client.reconnect_on_broken_connection = true;
+        assert!(!client.should_reconnect(&Status::unavailable("transport error")));
+        client.reconnect(None).unwrap();
+        assert!(client.read_client(Some("eu")).is_ok());
+    }
+
+    #[test]
+    fn detect_broken_connection() {
+        assert!(broken_connection(&Status::unknown(
+            "error trying to connect: connection reset by peer"
+        )));
+        assert!(broken_connection(&Status::unavailable("transport error")));
+        assert!(!broken_connection(&Status::unavailable(
+            "the service is unavailable"
+        )));
+        assert!(!broken_connection(&Status::invalid_argument(
+            "connection closed"
+        )));
+    }
+
+    #[test]
+    fn detect_location_mismatch() {
+        let status = Status::not_found("Not found: Dataset p:d was not found in location EU");
+        assert!(location_mismatch(&status));
+        assert!(!location_mismatch(&Status::not_found(
+            "Not found: Table p:d.t"
+        )));
+        assert!(!location_mismatch(&Status::invalid_argument(
+            "request failed: unrecognized name: location"
+        )));
+        assert!(!location_mismatch(&Status::permission_denied(
+            "Access denied in location EU"
+        )));
+    }
+
+    #[tokio::test]
+    async fn mock_project_location_mismatch() {
+        let mut client = MockBigQueryRead {
+            create_error: Some((
+                Code::NotFound,
+                "Not found: Dataset p:d was not found in location EU",
+            )),
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let billing_project = ProjectId::new("billing").unwrap();
+        let built = client
+            .read_session_builder(test_table())
+            .project_and_location(billing_project, "EU")
+            .build()
+            .await;
+        let err = built.err().unwrap();
+        assert_eq!(err.status().map(Status::code), Some(Code::NotFound));
+        assert!(matches!(
+            err,
+            Error::ProjectLocationMismatch { billing_project, location, .. }
+                if billing_project == "billing" && location == "EU"
+        ));
+    }
+
+    #[test]
+    fn detect_missing_permission() {
+        let status = Status::permission_denied(
+            "Permission bigquery.tables.getData denied on table p:d.t (or it may not exist).",
+        );
+        assert_eq!(
+            missing_permission(&status).as_deref(),
+            Some("bigquery.tables.getData")
+        );
+
+        let status = Status::permission_denied("request failed: the user does not have access");
+        assert_eq!(missing_permission(&status), None);
+
+        let status = Status::not_found("bigquery.tables.getData");
+        assert_eq!(missing_permission(&status), None);
+    }
+
+    #[test]
+    fn validate_project_id() {
+        assert!(ProjectId::new("openquery-public-testing").is_ok());
+        assert!(ProjectId::new("example.com:my-project").is_ok());
+        assert!(ProjectId::new("").is_err());
+        assert!(ProjectId::new("1project").is_err());
+        assert!(ProjectId::new("my_project").is_err());
+        assert!(ProjectId::new("project-").is_err());
+    }
+
+    #[test]
+    fn read_everything_omits_read_options() {
+        assert_eq!(table_read_options(None, None, None, None, None), None);
+
+        let read_options =
+            table_read_options(None, Some("id > 5".to_string()), None, None, None).unwrap();
+        assert_eq!(read_options.row_restriction, "id > 5");
+        assert!(read_options.selected_fields.is_empty());
+        assert_eq!(read_options.response_compression_codec, None);
+    }
+
+    #[test]
+    fn detect_unsupported_table_type() {
+        let status = Status::invalid_argument(
+            "request failed: projects/p/datasets/d/tables/t is a materialized view",
+        );
+        assert_eq!(unsupported_table_type(&status), Some("materialized view"));
+
+        let status = Status::invalid_argument("request failed: unrecognized name: review_count");
+        assert_eq!(unsupported_table_type(&status), None);
+
+        let status = Status::invalid_argument("request failed: unrecognized name: view");
+        assert_eq!(unsupported_table_type(&status), None);
+
+        let status = Status::not_found("view not found");
+        assert_eq!(unsupported_table_type(&status), None);
+    }
+
+    #[tokio::test]
+    async fn mock_unrelated_invalid_argument() {
+        let mut client = MockBigQueryRead {
+            create_error: Some((
+                Code::InvalidArgument,
+                "request failed: unrecognized name: view",
+            )),
+            ..Default::default()
+        }
+        .client()
+        .await;
+
+        let built = client.read_session_builder(test_table()).build().await;
+        assert!(matches!(
+            built,
+            Err(Error::Status(status)) if status.code() == Code::InvalidArgument
+        ));
+    }
+
+    #[test]
+    fn detect_invalid_row_restriction() {
+        let status = Status::invalid_argument(
+            "request failed: Row restriction contains an error: Unrecognized name: foo",
+        );
+        assert_eq!(
+            invalid_row_restriction(&status).as_deref(),
+            Some("Row restriction contains an error: Unrecognized name: foo")
+        );
+
+        let status = Status::invalid_argument("request failed: invalid table name");
+        assert_eq!(invalid_row_restriction(&status), None);
+    }
+
+    #[test]
+    fn retriable_errors() {
+        assert!(Error::from(Status::unavailable("")).is_retriable());
+        assert!(Error::from(Status::resource_exhausted("")).is_retriable());
+        assert!(!Error::from(Status::invalid_argument("")).is_retriable());
+        assert!(!Error::from(Status::permission_denied("")).is_retriable());
+        assert!(!Error::MissingSchema {}.is_retriable());
+    }
+
+    #[test]
+    fn read_rows_request_params() {
+        let req = ReadRowsRequest {
+            read_stream: "projects/p/locations/eu/sessions/s/streams/0".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            RequestParams::for_read_rows(&req).to_string(),
+            "read_stream=projects/p/locations/eu/sessions/s/streams/0"
+        );
+    }
+
     #[tokio::test]
     async fn read_a_table_with_arrow() {
         let sa_key = yup_oauth2::read_service_account_key("clientsecret.json")
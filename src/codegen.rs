@@ -0,0 +1,117 @@
+//! Generation of the source of a Rust struct holding the rows of a read session, given
+//! their Arrow schema, to deserialize the rows of
+//! [`RowsStreamReader::into_json_stream`](crate::read::RowsStreamReader::into_json_stream).
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::error::ArrowError;
+
+use crate::Error;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// A snake case identifier for the column `name`.
+fn identifier(name: &str) -> String {
+    let mut ident = String::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !ident.ends_with('_') {
+                ident.push('_');
+            }
+            ident.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            ident.push(c);
+        } else {
+            ident.push('_');
+        }
+    }
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    if KEYWORDS.contains(&ident.as_str()) {
+        // `crate` cannot be a raw identifier
+        match ident.as_str() {
+            "crate" => ident.push('_'),
+            _ => ident.insert_str(0, "r#"),
+        }
+    }
+    ident
+}
+
+/// The Rust type of the JSON values of the column `field`. Columns which are not numbers
+/// or booleans in JSON (e.g. `NUMERIC`, `DATE` or `TIMESTAMP`) are strings.
+fn rust_type(field: &Field) -> Result<String, Error> {
+    let rust_type = match field.data_type() {
+        DataType::Int64 => "i64".to_string(),
+        DataType::Float64 => "f64".to_string(),
+        DataType::Boolean => "bool".to_string(),
+        DataType::Utf8
+        | DataType::Binary
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _)
+        | DataType::Date32
+        | DataType::Time64(TimeUnit::Microsecond)
+        | DataType::Timestamp(TimeUnit::Microsecond, _) => "String".to_string(),
+        DataType::List(item) if !matches!(item.data_type(), DataType::Struct(_)) => {
+            format!("Vec<{}>", rust_type(item)?)
+        }
+        data_type => {
+            let err = ArrowError::SchemaError(format!(
+                "no Rust type for field {} of type {}: only flat schemas are supported",
+                field.name(),
+                data_type
+            ));
+            return Err(err.into());
+        }
+    };
+
+    // BigQuery arrays are never null, the items of an array cannot be either
+    if field.is_nullable() && !matches!(field.data_type(), DataType::List(_)) {
+        Ok(format!("Option<{}>", rust_type))
+    } else {
+        Ok(rust_type)
+    }
+}
+
+/// The source of a struct called `name` with a field for every column of `schema`.
+pub(crate) fn rust_struct(name: &str, schema: &Schema) -> Result<String, Error> {
+    let mut source = format!(
+        "#[derive(Debug, Clone, PartialEq, serde::Deserialize)]\npub struct {} {{\n",
+        name
+    );
+    for field in schema.fields() {
+        let ident = identifier(field.name());
+        if ident.trim_start_matches("r#") != field.name() {
+            source.push_str(&format!("    #[serde(rename = {:?})]\n", field.name()));
+        }
+        source.push_str(&format!("    pub {}: {},\n", ident, rust_type(field)?));
+    }
+    source.push_str("}\n");
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock::test_schema;
+
+    #[test]
+    fn test_schema_as_rust_struct() {
+        assert_eq!(
+            rust_struct("Row", &test_schema()).unwrap(),
+            "#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct Row {
+    pub id: i64,
+    pub name: Option<String>,
+}
+"
+        );
+        assert_eq!(identifier("userId"), "user_id");
+        assert_eq!(identifier("type"), "r#type");
+    }
+}
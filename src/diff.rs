@@ -0,0 +1,82 @@
+//! Comparison of two snapshots of a table by key, see
+//! [`Client::diff_snapshots`](crate::client::Client::diff_snapshots).
+use std::collections::HashSet;
+
+use arrow::array::{ArrayRef, BooleanArray};
+use arrow::compute::filter_record_batch;
+use arrow::record_batch::RecordBatch;
+use arrow::row::{RowConverter, Rows, SortField};
+
+use crate::Error;
+
+/// The columns of `batch` called `keys`.
+fn key_columns(batch: &RecordBatch, keys: &[String]) -> Result<Vec<ArrayRef>, Error> {
+    keys.iter()
+        .map(|key| {
+            batch
+                .column_by_name(key)
+                .cloned()
+                .ok_or_else(|| Error::InvalidOption {
+                    name: "keys",
+                    reason: format!("{:?} is not a top-level column", key),
+                })
+        })
+        .collect()
+}
+
+/// The rows of `batch`, whose keys are `rows`, with a key which is not in `others`.
+fn rows_not_in(batch: &RecordBatch, rows: &Rows, others: &Rows) -> Result<RecordBatch, Error> {
+    let others: HashSet<_> = others.iter().collect();
+    let keep: BooleanArray = rows
+        .iter()
+        .map(|row| Some(!others.contains(&row)))
+        .collect();
+    Ok(filter_record_batch(batch, &keep)?)
+}
+
+/// The rows of `after` with a key which is not in `before` (added), and the rows of
+/// `before` with a key which is not in `after` (removed).
+pub(crate) fn diff_by_keys(
+    before: &RecordBatch,
+    after: &RecordBatch,
+    keys: &[String],
+) -> Result<(RecordBatch, RecordBatch), Error> {
+    if keys.is_empty() {
+        return Err(Error::InvalidOption {
+            name: "keys",
+            reason: "at least one key column is needed".to_string(),
+        });
+    }
+    let before_keys = key_columns(before, keys)?;
+    let after_keys = key_columns(after, keys)?;
+
+    // Both sides are converted by the same converter, for their rows to be comparable
+    let fields = before_keys
+        .iter()
+        .map(|column| SortField::new(column.data_type().clone()))
+        .collect();
+    let converter = RowConverter::new(fields)?;
+    let before_rows = converter.convert_columns(&before_keys)?;
+    let after_rows = converter.convert_columns(&after_keys)?;
+
+    let added = rows_not_in(after, &after_rows, &before_rows)?;
+    let removed = rows_not_in(before, &before_rows, &after_rows)?;
+    Ok((added, removed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::mock::test_batch;
+
+    #[test]
+    fn diff_test_batches() {
+        let keys = vec!["id".to_string()];
+        let (added, removed) = diff_by_keys(&test_batch(0, 4), &test_batch(2, 4), &keys).unwrap();
+        assert_eq!(added, test_batch(4, 2));
+        assert_eq!(removed, test_batch(0, 2));
+
+        assert!(diff_by_keys(&test_batch(0, 4), &test_batch(2, 4), &[]).is_err());
+    }
+}
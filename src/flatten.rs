@@ -0,0 +1,166 @@
+//! Flattening of nested (`RECORD`) and repeated (`ARRAY`) columns into flat record
+//! batches, for consumers which only understand tables of scalars.
+use std::sync::Arc;
+
+use arrow::array::{make_array, Array, ArrayRef, AsArray, UInt32Array};
+use arrow::buffer::NullBuffer;
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Field, FieldRef, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+
+use crate::read::ListFlattening;
+use crate::Error;
+
+/// Replace the struct column `column` by its children, named `name.child`. Children are
+/// null wherever their parent is.
+fn expand_struct(field: &Field, column: &ArrayRef) -> Result<Vec<(FieldRef, ArrayRef)>, Error> {
+    let struct_array = column.as_struct();
+    let mut expanded = Vec::with_capacity(struct_array.num_columns());
+    for (child_field, child) in struct_array.fields().iter().zip(struct_array.columns()) {
+        let child = match struct_array.nulls() {
+            Some(parent_nulls) => {
+                let nulls = NullBuffer::union(Some(parent_nulls), child.nulls());
+                make_array(child.to_data().into_builder().nulls(nulls).build()?)
+            }
+            None => child.clone(),
+        };
+        let child_field = Field::new(
+            format!("{}.{}", field.name(), child_field.name()),
+            child_field.data_type().clone(),
+            child_field.is_nullable() || field.is_nullable(),
+        );
+        expanded.push((Arc::new(child_field), child));
+    }
+    Ok(expanded)
+}
+
+/// Explode the list column at `index`, like `LEFT JOIN UNNEST` would: every item of a
+/// list gets its own row, and rows with a null or empty list are kept with a null item.
+fn explode_list(
+    fields: &mut [FieldRef],
+    columns: &mut [ArrayRef],
+    index: usize,
+) -> Result<(), Error> {
+    let list = columns[index].as_list::<i32>();
+
+    let mut rows = Vec::new();
+    let mut items = Vec::new();
+    for (row, offsets) in list.value_offsets().windows(2).enumerate() {
+        let (start, end) = (offsets[0] as u32, offsets[1] as u32);
+        if list.is_null(row) || start == end {
+            rows.push(row as u32);
+            items.push(None);
+        } else {
+            for item in start..end {
+                rows.push(row as u32);
+                items.push(Some(item));
+            }
+        }
+    }
+
+    let rows = UInt32Array::from(rows);
+    let items = UInt32Array::from(items);
+    let exploded = take(list.values().as_ref(), &items, None)?;
+    for (i, column) in columns.iter_mut().enumerate() {
+        if i != index {
+            *column = take(column.as_ref(), &rows, None)?;
+        }
+    }
+
+    let item_field = match fields[index].data_type() {
+        DataType::List(item_field) => item_field.clone(),
+        _ => unreachable!("only list columns are exploded"),
+    };
+    fields[index] = Arc::new(Field::new(
+        fields[index].name(),
+        item_field.data_type().clone(),
+        true,
+    ));
+    columns[index] = exploded;
+    Ok(())
+}
+
+/// Flatten the struct columns of `batch`, and its list columns depending on `lists`.
+pub(crate) fn flatten_record_batch(
+    batch: &RecordBatch,
+    lists: ListFlattening,
+) -> Result<RecordBatch, Error> {
+    let schema = batch.schema();
+    let mut fields: Vec<FieldRef> = schema.fields().iter().cloned().collect();
+    let mut columns: Vec<ArrayRef> = batch.columns().to_vec();
+
+    loop {
+        let nested = fields.iter().position(|field| match field.data_type() {
+            DataType::Struct(_) => true,
+            DataType::List(_) => lists == ListFlattening::Explode,
+            _ => false,
+        });
+        let index = match nested {
+            Some(index) => index,
+            None => break,
+        };
+
+        if let DataType::Struct(_) = fields[index].data_type() {
+            let expanded = expand_struct(&fields[index], &columns[index])?;
+            let (expanded_fields, expanded_columns): (Vec<_>, Vec<_>) =
+                expanded.into_iter().unzip();
+            fields.splice(index..=index, expanded_fields);
+            columns.splice(index..=index, expanded_columns);
+        } else {
+            explode_list(&mut fields, &mut columns, index)?;
+        }
+    }
+
+    let num_rows = columns
+        .first()
+        .map(|column| column.len())
+        .unwrap_or(batch.num_rows());
+    let options = RecordBatchOptions::new().with_row_count(Some(num_rows));
+    let batch = RecordBatch::try_new_with_options(
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone())),
+        columns,
+        &options,
+    )?;
+    Ok(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use arrow::array::{Int64Array, ListArray, StringArray, StructArray};
+    use arrow::datatypes::Int64Type;
+
+    #[test]
+    fn flatten_structs_and_explode_lists() {
+        let city = Arc::new(StringArray::from(vec![Some("London"), Some("Paris")])) as ArrayRef;
+        let address = StructArray::from(vec![(
+            Arc::new(Field::new("city", DataType::Utf8, true)),
+            city,
+        )]);
+        let scores = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            None,
+        ]);
+        let batch = RecordBatch::try_from_iter(vec![
+            ("address", Arc::new(address) as ArrayRef),
+            ("scores", Arc::new(scores) as ArrayRef),
+        ])
+        .unwrap();
+
+        let flat = flatten_record_batch(&batch, ListFlattening::Explode).unwrap();
+
+        let expected = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![
+                Field::new("address.city", DataType::Utf8, true),
+                Field::new("scores", DataType::Int64, true),
+            ])),
+            vec![
+                Arc::new(StringArray::from(vec!["London", "London", "Paris"])),
+                Arc::new(Int64Array::from(vec![Some(1), Some(2), None])),
+            ],
+        )
+        .unwrap();
+        assert_eq!(flat, expected);
+    }
+}
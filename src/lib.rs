@@ -60,6 +60,54 @@
 //!     Ok(())
 //! }
 //! ```
+//! For small tables, [`read_table`](crate::client::read_table) does all of the above in
+//! one call, returning all the record batches of the table:
+//! ```rust
+//! # async fn run(auth: yup_oauth2::authenticator::DefaultAuthenticator) -> Result<(), bigquery_storage::Error> {
+//! let table = bigquery_storage::Table::new("bigquery-public-data", "london_bicycles", "cycle_stations");
+//! let batches = bigquery_storage::read_table(auth, table, "openquery-public-testing").await?;
+//! # Ok(())
+//! # }
+//! ```
+//! # Type mapping
+//! Columns are decoded into the Arrow types chosen by the server:
+//!
+//! | BigQuery | Arrow |
+//! |---|---|
+//! | `INT64` | `Int64` |
+//! | `FLOAT64` | `Float64` |
+//! | `BOOL` | `Boolean` |
+//! | `STRING`, `GEOGRAPHY` (as WKT), `JSON` | `Utf8` |
+//! | `BYTES` | `Binary` |
+//! | `NUMERIC` | `Decimal128(38, 9)` |
+//! | `BIGNUMERIC` | `Decimal256(76, 38)` |
+//! | `DATE` | `Date32` |
+//! | `TIME` | `Time64(Microsecond)` |
+//! | `DATETIME` | `Timestamp(Microsecond, None)` |
+//! | `TIMESTAMP` | `Timestamp(Microsecond, Some("UTC"))` |
+//! | `ARRAY` | `List` |
+//! | `STRUCT` | `Struct` |
+//!
+//! In particular, `NUMERIC` and `BIGNUMERIC` columns are exact decimals with the precision
+//! and scale of the session schema, never floats or raw bytes.
+//! # Async streams
+//! Besides the synchronous Arrow `StreamReader` of `RowsStreamReader::into_arrow_reader`, rows are read as streams of record batches (e.g. `RowsStreamReader::into_stream`, `ReadSession::into_stream`), which implement `futures::Stream`. They are all boxed, hence `Unpin`, and `Send`: they need no pinning, can be moved to `tokio::spawn` or polled in `tokio::select!`, and compose with the adapters of `futures::StreamExt` and `tokio_stream::StreamExt` (e.g. `chunks_timeout` or `throttle`, with the `time` feature of `tokio-stream`). Rows are only downloaded as the streams are polled, so a slow consumer slows down the download rather than buffering rows, except for the streams of `RowsStreamReader::into_buffered_stream` and `RowsStreamReader::tee`, which buffer a bounded number of batches.
+//! # Views
+//! The Storage API only reads tables. Reading a view (or a materialized view, or an external table) fails with `Error::UnsupportedTableType`. To read one, run its query first, e.g. a query job with `SELECT * FROM` the view, and read the destination table of the job (or the anonymous table holding its results) with this crate. This crate does not run queries itself.
+//! # Without Arrow
+//! Decoding rows into Arrow requires the `arrow` feature, which is enabled by default. Without it, streams are consumed with `RowsStreamReader::into_raw_responses`, which yields the responses of the server with their rows still serialized.
+//! # JSON
+//! The `json` feature adds `RowsStreamReader::into_json_stream`, which yields rows as JSON objects, e.g. for debugging or small exports.
+//! # CSV
+//! The `csv` feature adds `RowsStreamReader::write_csv`, which writes the rows of a stream as CSV.
+//! # Sinks
+//! `ReadSession::drain_into` writes all the rows of a session into a `RecordBatchSink`, e.g. a table format writer of your own. The `parquet` feature adds `ParquetSink`, which writes a Parquet file, and `PartitionedParquetSink`, which rolls over to a new file every so many rows (see `ReadSession::write_parquet_partitioned`).
+//! # Retries
+//! Failed streams can be resumed (`ReadSessionBuilder::max_resume_attempts`) and failed session creations retried (`ReadSessionBuilder::max_create_retries`). Retries are spaced by a `Backoff`, which can be replaced with a strategy of your own through `ReadSessionBuilder::backoff`.
+//! # Blocking API
+//! If you would rather not deal with an async runtime, the `blocking` feature enables the `blocking` module, which wraps the API described above behind synchronous calls.
+//! # Arrow Flight
+//! The `flight` feature adds `RowsStreamReader::into_flight_data`, which turns a stream into Arrow Flight messages without decoding its rows.
 //! # Authentication
 //! For authentication you need an [Authenticator](yup_oauth2::authenticator::Authenticator), which is provided by the [yup_oauth2](yup_oauth2) crate.
 pub use yup_oauth2;
@@ -75,15 +123,52 @@ pub use client::*;
 pub mod read;
 pub use read::*;
 
+pub mod backoff;
+pub use backoff::*;
+
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
+#[cfg(feature = "arrow")]
+pub mod sink;
+#[cfg(feature = "arrow")]
+pub use sink::*;
+
+mod rpc;
+pub use rpc::DebugInfo;
+
+#[cfg(feature = "arrow")]
+mod avro;
+
+#[cfg(feature = "arrow")]
+mod flatten;
+
+#[cfg(feature = "arrow")]
+mod codegen;
+
+#[cfg(feature = "arrow")]
+mod diff;
+
+#[cfg(all(test, feature = "arrow"))]
+mod mock;
+
 macro_rules! errors {
-    { $(
-        $(#[$m:meta])*
-        $id:ident($p:path),
-    )* } => {
+    {
+        $(
+            $(#[$m:meta])*
+            $id:ident($p:path),
+        )*
+        ;
+        $(
+            $(#[$sm:meta])*
+            $sid:ident { $($field:ident: $ty:ty),* } => $fmt:literal,
+        )*
+    } => {
         /// Encompassing error enum for this crate.
         #[derive(Debug)]
         pub enum Error {
             $($(#[$m])* $id($p),)*
+            $($(#[$sm])* $sid { $($field: $ty),* },)*
         }
 
         impl std::fmt::Display for Error {
@@ -95,6 +180,11 @@ macro_rules! errors {
                             write!(f, "{}: {}", stringify!($id), inner)
                         },
                     )*
+                    $(
+                        Self::$sid { $($field),* } => {
+                            write!(f, concat!("{}: ", $fmt), stringify!($sid), $($field = $field),*)
+                        },
+                    )*
                 }
             }
         }
@@ -121,10 +211,97 @@ errors! {
     Io(std::io::Error),
     #[cfg(feature = "arrow")]
     Arrow(arrow::error::ArrowError),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
+    ;
+    #[doc = "An option was given an invalid value."]
+    InvalidOption { name: &'static str, reason: String } => "invalid value for {name}: {reason}",
+    #[doc = "The schema sent along the rows does not match the schema of the read session."]
+    SchemaMismatch { expected: String, found: String } => "expected schema {expected}, found {found}",
+    #[doc = "More bytes than allowed by [`ReadSessionBuilder::max_bytes`](crate::client::ReadSessionBuilder::max_bytes) were downloaded."]
+    ByteLimitExceeded { limit: u64 } => "downloaded more than {limit} bytes",
+    #[doc = "A batch of rows could not be decoded. `offset` is the number of bytes of the stream received before this batch."]
+    BatchDecode { stream: String, batch_index: usize, offset: u64, source: Box<Error> } => "in stream {stream}, batch {batch_index} (at byte {offset}): {source}",
+    #[doc = "The rows sent by the server could not be decompressed."]
+    Decompression { reason: String } => "could not decompress rows: {reason}",
+    #[doc = "The server did not send the schema of the read session."]
+    MissingSchema {} => "the server did not send the schema of the read session",
+    #[doc = "The server sent a response without any rows."]
+    MissingRows {} => "the server sent a response without rows",
+    #[doc = "A serialized Arrow message sent by the server is malformed."]
+    InvalidArrowMessage { reason: String } => "{reason}",
+    #[doc = "The read session uses a data format (i.e. Avro) which cannot be decoded by this crate."]
+    UnsupportedDataFormat { format: &'static str } => "the {format} data format cannot be read as Arrow",
+    #[doc = "The [`row_restriction`](crate::client::ReadSessionBuilder::row_restriction) was rejected by the server. `reason` is the part of the server's message about the restriction, e.g. an unknown column or a syntax error."]
+    InvalidRowRestriction { reason: String } => "{reason}",
+    #[doc = "The table is of a type (e.g. a view) that cannot be read with the Storage API. Such tables have to be read through a query, e.g. with the [`jobs.query`](https://cloud.google.com/bigquery/docs/reference/rest/v2/jobs/query) API, the result of which can then be read with this crate."]
+    UnsupportedTableType { table: String, table_type: &'static str } => "{table} is a {table_type}, which cannot be read with the Storage API: query it instead",
+    #[doc = "A stream returned no rows, while [`ReadSessionBuilder::strict_nonempty_streams`](crate::client::ReadSessionBuilder::strict_nonempty_streams) was set."]
+    EmptyStream { stream: String } => "stream {stream} returned no rows",
+    #[doc = "A read session could not be created without going over the rate set by [`Client::with_session_rate_limit`](crate::client::Client::with_session_rate_limit)."]
+    SessionRateLimited { sessions_per_second: f64 } => "over the limit of {sessions_per_second} read sessions created per second",
+    #[doc = "The credentials of the client lack an IAM `permission` (e.g. `bigquery.readsessions.create`), see [`Client::check_read_access`](crate::client::Client::check_read_access)."]
+    MissingPermission { permission: String } => "missing the IAM permission {permission}",
+    #[doc = "A read would expose `column`, which is denied by [`Client::with_column_denylist`](crate::client::Client::with_column_denylist)."]
+    ForbiddenColumn { column: String } => "the column {column} is not allowed to be read",
+    #[doc = "The billing project, the project of the table and the location of a read session are not consistent, e.g. the dataset of the table is not in `location`. `status` is the status the server rejected the session with, also returned by [`Error::status`](Error::status). See [`ReadSessionBuilder::project_and_location`](crate::client::ReadSessionBuilder::project_and_location)."]
+    ProjectLocationMismatch { billing_project: String, data_project: String, location: String, status: tonic::Status } => "cannot read a table of {data_project} in {location}, billed to {billing_project}: {status}",
+    #[doc = "An error shared by all the streams of [`RowsStreamReader::tee`](crate::read::RowsStreamReader::tee)."]
+    Shared { source: std::sync::Arc<Error> } => "{source}",
 }
 
 impl Error {
-    pub(crate) fn invalid<S: AsRef<str>>(s: S) -> Self {
-        Self::InvalidResponse(s.as_ref().to_string())
+    /// Whether the operation that failed with this error is worth trying again, as is.
+    /// This is the case of statuses reporting transient conditions on the server side
+    /// (`UNAVAILABLE`, `DEADLINE_EXCEEDED`, `ABORTED`, `RESOURCE_EXHAUSTED`) and of
+    /// connection failures. Anything else (e.g. `INVALID_ARGUMENT`, `NOT_FOUND` or
+    /// `PERMISSION_DENIED`) is deemed permanent.
+    pub fn is_retriable(&self) -> bool {
+        use tonic::Code;
+        match self {
+            Self::Status(status) => matches!(
+                status.code(),
+                Code::Unavailable
+                    | Code::DeadlineExceeded
+                    | Code::Aborted
+                    | Code::ResourceExhausted
+            ),
+            Self::Transport(_) => true,
+            Self::BatchDecode { source, .. } => source.is_retriable(),
+            Self::Shared { source } => source.is_retriable(),
+            _ => false,
+        }
+    }
+
+    /// The status the server failed the call with, if that is what this error is about,
+    /// looking through the errors wrapping it (e.g. [`Error::BatchDecode`](Error::BatchDecode)).
+    pub fn status(&self) -> Option<&tonic::Status> {
+        match self {
+            Self::Status(status) => Some(status),
+            Self::ProjectLocationMismatch { status, .. } => Some(status),
+            Self::BatchDecode { source, .. } => source.status(),
+            Self::Shared { source } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// The `google.rpc.DebugInfo` the server attached to its status, if any, which Google
+    /// support may ask for.
+    pub fn debug_info(&self) -> Option<DebugInfo> {
+        self.status().and_then(rpc::debug_info)
+    }
+
+    /// The text metadata the server sent along its status (e.g. request IDs), to be
+    /// logged or reported to Google support. Binary entries are left out.
+    pub fn status_metadata(&self) -> Vec<(String, String)> {
+        self.status().map(rpc::metadata).unwrap_or_default()
+    }
+
+    pub(crate) fn invalid_arrow<S: AsRef<str>>(s: S) -> Self {
+        Self::InvalidArrowMessage {
+            reason: s.as_ref().to_string(),
+        }
     }
 }
@@ -0,0 +1,334 @@
+//! A mock of the BigQuery Storage API serving canned Arrow data, so that the client and
+//! the decoding logic can be tested without network or credentials.
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arrow::array::{Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::writer::{IpcWriteOptions, StreamWriter};
+use arrow::ipc::CompressionType;
+use arrow::record_batch::RecordBatch;
+
+use futures::stream::{self, Stream, StreamExt};
+use hyper::client::HttpConnector;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+
+use crate::googleapis::big_query_read_server::{BigQueryRead, BigQueryReadServer};
+use crate::googleapis::{
+    read_rows_response, read_session, stream_stats, ArrowRecordBatch, ArrowSchema,
+    CreateReadSessionRequest, ReadRowsRequest, ReadRowsResponse, ReadSession, ReadStream,
+    SplitReadStreamRequest, SplitReadStreamResponse, StreamStats,
+};
+use crate::Client;
+
+/// Serialize `schema` as a single IPC message, the way the API does.
+pub(crate) fn serialize_schema(schema: &Schema) -> Vec<u8> {
+    let mut buf = Vec::new();
+    {
+        let mut writer = StreamWriter::try_new(&mut buf, schema).unwrap();
+        writer.finish().unwrap();
+    }
+    // Drop the end of stream marker (continuation bytes + zero length)
+    buf.truncate(buf.len() - 8);
+    buf
+}
+
+/// Serialize `batch` as a single IPC message, the way the API does, with its buffers
+/// compressed with `compression` if set.
+pub(crate) fn serialize_batch(
+    batch: &RecordBatch,
+    compression: Option<CompressionType>,
+) -> Vec<u8> {
+    let schema = batch.schema();
+    let schema_len = serialize_schema(&schema).len();
+
+    let options = IpcWriteOptions::default()
+        .try_with_compression(compression)
+        .unwrap();
+    let mut buf = Vec::new();
+    {
+        let mut writer = StreamWriter::try_new_with_options(&mut buf, &schema, options).unwrap();
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+    }
+    buf.truncate(buf.len() - 8);
+    buf.split_off(schema_len)
+}
+
+/// A small two columns schema.
+pub(crate) fn test_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, true),
+    ]))
+}
+
+/// A batch of `len` rows following [`test_schema`](test_schema), with ids starting at `start`.
+pub(crate) fn test_batch(start: i64, len: i64) -> RecordBatch {
+    let ids: Vec<i64> = (start..start + len).collect();
+    let names: Vec<Option<String>> = ids
+        .iter()
+        .map(|id| {
+            if id % 2 == 0 {
+                Some(id.to_string())
+            } else {
+                None
+            }
+        })
+        .collect();
+    RecordBatch::try_new(
+        test_schema(),
+        vec![
+            Arc::new(Int64Array::from(ids)),
+            Arc::new(StringArray::from(
+                names.iter().map(|name| name.as_deref()).collect::<Vec<_>>(),
+            )),
+        ],
+    )
+    .unwrap()
+}
+
+/// The mock service. Every stream of a session serves the same batches.
+#[derive(Clone)]
+pub(crate) struct MockBigQueryRead {
+    pub(crate) schema: SchemaRef,
+    pub(crate) batches: Vec<RecordBatch>,
+    pub(crate) stream_count: usize,
+    /// If set, the first stream read fails with `ABORTED` after that many responses.
+    pub(crate) abort_after: Option<usize>,
+    /// The code of the failure of [`abort_after`](MockBigQueryRead::abort_after).
+    pub(crate) abort_code: Code,
+    /// If set, the status every session creation fails with.
+    pub(crate) create_error: Option<(Code, &'static str)>,
+    /// If set, streams end without an error after that many responses, as if the server
+    /// closed them early.
+    pub(crate) end_after: Option<usize>,
+    /// If set, the progress reported at the end of the last batch of a stream, instead of
+    /// exactly `1.0`.
+    pub(crate) final_progress: Option<f64>,
+    /// Whether the schema is sent along the first response of each stream, rather than
+    /// with the session.
+    pub(crate) schema_in_rows: bool,
+    /// The codec the buffers of the batches are compressed with, if any.
+    pub(crate) buffer_compression: Option<CompressionType>,
+    /// Whether the serialized rows are compressed with LZ4, as for the `LZ4` response
+    /// compression codec.
+    pub(crate) lz4_rows: bool,
+    /// Whether streams are kept open after their last batch, until the client cancels
+    /// them.
+    pub(crate) hang_after_rows: bool,
+    /// The number of `ReadRows` calls currently being served.
+    pub(crate) open_streams: Arc<AtomicUsize>,
+    /// Whether a stream has already been aborted, so that only the first attempt fails.
+    pub(crate) aborted: Arc<AtomicBool>,
+}
+
+/// Counts a `ReadRows` call as open until it is dropped.
+struct OpenStream(Arc<AtomicUsize>);
+
+impl OpenStream {
+    fn new(open_streams: &Arc<AtomicUsize>) -> Self {
+        open_streams.fetch_add(1, Ordering::SeqCst);
+        Self(open_streams.clone())
+    }
+}
+
+impl Drop for OpenStream {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for MockBigQueryRead {
+    fn default() -> Self {
+        Self {
+            schema: test_schema(),
+            batches: vec![test_batch(0, 3), test_batch(3, 3)],
+            stream_count: 2,
+            abort_after: None,
+            abort_code: Code::Aborted,
+            create_error: None,
+            end_after: None,
+            final_progress: None,
+            schema_in_rows: false,
+            buffer_compression: None,
+            lz4_rows: false,
+            hang_after_rows: false,
+            open_streams: Arc::default(),
+            aborted: Arc::default(),
+        }
+    }
+}
+
+impl MockBigQueryRead {
+    fn session_name(table: &str) -> String {
+        format!("{}/sessions/mock", table)
+    }
+
+    /// The responses of a stream read from row `offset`, which has to be at the start of
+    /// a batch.
+    fn responses(&self, offset: i64) -> Vec<Result<ReadRowsResponse, Status>> {
+        let total_rows: usize = self.batches.iter().map(|batch| batch.num_rows()).sum();
+        let mut rows = 0;
+        let mut responses: Vec<_> = self
+            .batches
+            .iter()
+            .filter_map(|batch| {
+                let start = rows;
+                rows += batch.num_rows() as i64;
+                Some((batch, start)).filter(|_| rows > offset)
+            })
+            .map(|(batch, start)| {
+                let mut serialized_record_batch = serialize_batch(batch, self.buffer_compression);
+                let mut uncompressed_byte_size = None;
+                if self.lz4_rows {
+                    uncompressed_byte_size = Some(serialized_record_batch.len() as i64);
+                    serialized_record_batch = lz4_flex::compress(&serialized_record_batch);
+                }
+                let progress = stream_stats::Progress {
+                    at_response_start: start as f64 / total_rows as f64,
+                    at_response_end: (start + batch.num_rows() as i64) as f64 / total_rows as f64,
+                };
+                Ok(ReadRowsResponse {
+                    row_count: batch.num_rows() as i64,
+                    uncompressed_byte_size,
+                    stats: Some(StreamStats {
+                        progress: Some(progress),
+                    }),
+                    rows: Some(read_rows_response::Rows::ArrowRecordBatch(
+                        ArrowRecordBatch {
+                            serialized_record_batch,
+                            ..Default::default()
+                        },
+                    )),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        if let (Some(final_progress), Some(Ok(last))) = (self.final_progress, responses.last_mut())
+        {
+            if let Some(progress) = last
+                .stats
+                .as_mut()
+                .and_then(|stats| stats.progress.as_mut())
+            {
+                progress.at_response_end = final_progress;
+            }
+        }
+
+        if let Some(end_after) = self.end_after {
+            responses.truncate(end_after);
+        }
+
+        if self.schema_in_rows {
+            if let Some(Ok(first)) = responses.first_mut() {
+                first.schema = Some(read_rows_response::Schema::ArrowSchema(ArrowSchema {
+                    serialized_schema: serialize_schema(&self.schema),
+                }));
+            }
+        }
+
+        if let Some(abort_after) = self.abort_after {
+            if abort_after < responses.len() && !self.aborted.swap(true, Ordering::SeqCst) {
+                responses.truncate(abort_after);
+                responses.push(Err(Status::new(self.abort_code, "mock abort")));
+            }
+        }
+        responses
+    }
+
+    /// Serve this mock on a random local port, returning the endpoint to connect to.
+    pub(crate) async fn serve(self) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(
+            Server::builder()
+                .add_service(BigQueryReadServer::new(self))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+        endpoint
+    }
+
+    /// Serve this mock and connect a [`Client`](crate::Client) to it.
+    pub(crate) async fn client(self) -> Client<HttpConnector> {
+        let endpoint = self.serve().await;
+        Client::builder().endpoint(endpoint).build().await.unwrap()
+    }
+}
+
+#[tonic::async_trait]
+impl BigQueryRead for MockBigQueryRead {
+    async fn create_read_session(
+        &self,
+        request: Request<CreateReadSessionRequest>,
+    ) -> Result<Response<ReadSession>, Status> {
+        if let Some((code, message)) = self.create_error {
+            return Err(Status::new(code, message));
+        }
+        let mut read_session = request
+            .into_inner()
+            .read_session
+            .ok_or_else(|| Status::invalid_argument("missing read_session"))?;
+        // As the server does, read the table as of now unless told otherwise
+        let table_modifiers = read_session
+            .table_modifiers
+            .get_or_insert_with(Default::default);
+        if table_modifiers.snapshot_time.is_none() {
+            table_modifiers.snapshot_time = Some(SystemTime::now().into());
+        }
+
+        let name = Self::session_name(&read_session.table);
+        let streams = (0..self.stream_count)
+            .map(|i| ReadStream {
+                name: format!("{}/streams/{}", name, i),
+                ..Default::default()
+            })
+            .collect();
+        let schema = read_session::Schema::ArrowSchema(ArrowSchema {
+            serialized_schema: serialize_schema(&self.schema),
+        });
+        let schema = Some(schema).filter(|_| !self.schema_in_rows);
+
+        Ok(Response::new(ReadSession {
+            name,
+            schema,
+            streams,
+            ..read_session
+        }))
+    }
+
+    type ReadRowsStream =
+        Pin<Box<dyn Stream<Item = Result<ReadRowsResponse, Status>> + Send + Sync>>;
+
+    async fn read_rows(
+        &self,
+        request: Request<ReadRowsRequest>,
+    ) -> Result<Response<Self::ReadRowsStream>, Status> {
+        let offset = request.into_inner().offset;
+        let open_stream = OpenStream::new(&self.open_streams);
+        let responses = stream::iter(self.responses(offset));
+        let responses: Self::ReadRowsStream = if self.hang_after_rows {
+            Box::pin(responses.chain(stream::pending()))
+        } else {
+            Box::pin(responses)
+        };
+        let responses = responses.map(move |response| {
+            let _open = &open_stream;
+            response
+        });
+        Ok(Response::new(Box::pin(responses)))
+    }
+
+    async fn split_read_stream(
+        &self,
+        _request: Request<SplitReadStreamRequest>,
+    ) -> Result<Response<SplitReadStreamResponse>, Status> {
+        Err(Status::unimplemented("split_read_stream"))
+    }
+}
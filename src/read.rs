@@ -1,80 +1,1197 @@
 use tonic::Streaming;
 
-use futures::future::ready;
-use futures::stream::{StreamExt, TryStreamExt};
+use futures::future::{ready, BoxFuture};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 
+use std::collections::HashMap;
 use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
 
 use crate::googleapis::{
-    read_rows_response::Rows, read_session::Schema, ArrowRecordBatch, ArrowSchema, ReadRowsResponse,
+    read_rows_response::{Rows, Schema as RowsSchema},
+    read_session::Schema,
+    stream_stats::Progress,
+    ArrowRecordBatch, ArrowSchema, ReadRowsResponse,
 };
+use tonic::{Code, Status};
+
+use crate::backoff::{next_delay, SharedBackoff};
+#[cfg(feature = "arrow")]
+use crate::flatten::flatten_record_batch;
 use crate::Error;
 
 #[cfg(feature = "arrow")]
-use arrow::ipc::reader::StreamReader as ArrowStreamReader;
+use arrow::array::BooleanArray;
+#[cfg(feature = "arrow")]
+use arrow::buffer::Buffer;
+#[cfg(feature = "arrow")]
+use arrow::compute::{concat_batches, filter_record_batch};
+#[cfg(feature = "arrow")]
+use arrow::datatypes::{DataType, Field, Schema as ArrowSchemaRepr, SchemaRef};
+#[cfg(feature = "arrow")]
+use arrow::error::ArrowError;
+#[cfg(feature = "arrow")]
+use arrow::ipc::reader::{read_record_batch, StreamReader as ArrowStreamReader};
+#[cfg(feature = "arrow")]
+use arrow::ipc::CompressionType;
+#[cfg(feature = "arrow")]
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+
+#[cfg(feature = "flight")]
+use arrow_flight::FlightData;
+#[cfg(feature = "flight")]
+use bytes::Bytes;
 
 /// Remove the continuation bytes segment of a valid Arrow IPC message
 #[cfg(feature = "arrow")]
-fn strip_continuation_bytes(msg: &[u8]) -> Result<&[u8], Error> {
+pub(crate) fn strip_continuation_bytes(msg: &[u8]) -> Result<&[u8], Error> {
     let header = msg
         .get(0..4)
-        .ok_or(Error::invalid("arrow message of invalid len"))?;
+        .ok_or(Error::invalid_arrow("arrow message of invalid len"))?;
     if header != [255; 4] {
-        Err(Error::invalid("invalid arrow message"))
+        Err(Error::invalid_arrow("invalid arrow message"))
     } else {
-        let tail = msg.get(4..).ok_or(Error::invalid("empty arrow message"))?;
+        let tail = msg
+            .get(4..)
+            .ok_or(Error::invalid_arrow("empty arrow message"))?;
         Ok(tail)
     }
 }
 
+/// Decompress the serialized rows of a response, if the server compressed them. The
+/// only codec supported by the API is raw LZ4, in which case `uncompressed_len` is set.
+#[cfg(feature = "arrow")]
+fn decompress_rows(rows: Vec<u8>, uncompressed_len: i64) -> Result<Vec<u8>, Error> {
+    if uncompressed_len <= 0 {
+        return Ok(rows);
+    }
+    lz4_flex::decompress(&rows, uncompressed_len as usize).map_err(|e| Error::Decompression {
+        reason: e.to_string(),
+    })
+}
+
+/// Decode a single serialized record batch message, using the already deserialized
+/// `schema` of the stream.
+#[cfg(feature = "arrow")]
+fn decode_record_batch(schema: &SchemaRef, msg: &[u8]) -> Result<RecordBatch, Error> {
+    let msg = strip_continuation_bytes(msg)?;
+
+    // An encapsulated message is the length of its metadata, the metadata itself
+    // (a flatbuffer) and then the body of the message.
+    let meta_len = msg
+        .get(0..4)
+        .ok_or(Error::invalid_arrow("arrow message of invalid len"))?;
+    let meta_len = i32::from_le_bytes([meta_len[0], meta_len[1], meta_len[2], meta_len[3]]);
+    let meta_end = 4 + meta_len.max(0) as usize;
+    let meta = msg
+        .get(4..meta_end)
+        .ok_or(Error::invalid_arrow("truncated arrow message"))?;
+
+    let message = arrow::ipc::root_as_message(meta)
+        .map_err(|e| Error::invalid_arrow(format!("invalid arrow message: {}", e)))?;
+    let batch = message
+        .header_as_record_batch()
+        .ok_or(Error::invalid_arrow("expected arrow record batch"))?;
+    if let Some(compression) = batch.compression() {
+        let codec = compression.codec();
+        if codec != CompressionType::LZ4_FRAME && codec != CompressionType::ZSTD {
+            return Err(Error::Decompression {
+                reason: format!("unsupported Arrow buffer compression {:?}", codec),
+            });
+        }
+    }
+    let body_end = meta_end + message.bodyLength().max(0) as usize;
+    let body = msg
+        .get(meta_end..body_end)
+        .ok_or(Error::invalid_arrow("truncated arrow message"))?;
+
+    let batch = read_record_batch(
+        &Buffer::from(body),
+        batch,
+        schema.clone(),
+        &HashMap::new(),
+        None,
+        &message.version(),
+    )?;
+    Ok(batch)
+}
+
+/// Decodes the serialized record batches of a stream.
+#[cfg(feature = "arrow")]
+struct BatchDecoder {
+    schema: SchemaRef,
+    /// The serialized schema of the session, when `schema` was given by the user instead
+    /// of being deserialized from it. It is only checked if decoding fails.
+    unchecked_schema: Option<Vec<u8>>,
+}
+
+#[cfg(feature = "arrow")]
+impl BatchDecoder {
+    fn new(serialized_schema: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            schema: decode_schema(serialized_schema)?,
+            unchecked_schema: None,
+        })
+    }
+
+    fn with_known_schema(schema: SchemaRef, serialized_schema: Vec<u8>) -> Self {
+        Self {
+            schema,
+            unchecked_schema: Some(serialized_schema),
+        }
+    }
+
+    fn decode(&self, msg: &[u8]) -> Result<RecordBatch, Error> {
+        decode_record_batch(&self.schema, msg).map_err(|err| {
+            let serialized_schema = match &self.unchecked_schema {
+                Some(serialized_schema) => serialized_schema,
+                None => return err,
+            };
+            match decode_schema(serialized_schema) {
+                Ok(schema) if schema != self.schema => Error::SchemaMismatch {
+                    expected: describe_schema(&schema),
+                    found: describe_schema(&self.schema),
+                },
+                _ => err,
+            }
+        })
+    }
+}
+
+/// Decode a serialized (already stripped) schema message.
+#[cfg(feature = "arrow")]
+pub(crate) fn decode_schema(schema: &[u8]) -> Result<SchemaRef, Error> {
+    let mut buf = Vec::with_capacity(schema.len() + 4);
+    buf.extend(schema);
+    buf.extend(&[0u8; 4]);
+
+    let reader = ArrowStreamReader::try_new_unbuffered(Cursor::new(buf), None)?;
+    Ok(reader.schema())
+}
+
+/// `schema` without any metadata, on the schema itself or on its fields, nested ones
+/// included.
+#[cfg(feature = "arrow")]
+pub(crate) fn strip_schema_metadata(schema: &ArrowSchemaRepr) -> SchemaRef {
+    fn strip_field(field: &Field) -> Field {
+        let data_type = match field.data_type() {
+            DataType::Struct(fields) => {
+                DataType::Struct(fields.iter().map(|field| strip_field(field)).collect())
+            }
+            DataType::List(item) => DataType::List(Arc::new(strip_field(item))),
+            data_type => data_type.clone(),
+        };
+        Field::new(field.name(), data_type, field.is_nullable())
+    }
+    let fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| strip_field(field))
+        .collect();
+    Arc::new(ArrowSchemaRepr::new(fields))
+}
+
+#[cfg(feature = "arrow")]
+fn describe_schema(schema: &ArrowSchemaRepr) -> String {
+    let fields: Vec<String> = schema
+        .fields()
+        .iter()
+        .map(|field| format!("{}: {:?}", field.name(), field.data_type()))
+        .collect();
+    format!("[{}]", fields.join(", "))
+}
+
+/// Check that the schema sent along the rows (if any) matches the schema of the
+/// read session, which is what batches are decoded with.
+#[cfg(feature = "arrow")]
+fn check_schema(expected: &[u8], found: &[u8]) -> Result<(), Error> {
+    let found = strip_continuation_bytes(found)?;
+    if expected == found {
+        return Ok(());
+    }
+
+    let expected = decode_schema(expected)?;
+    let found = decode_schema(found)?;
+    if expected != found {
+        Err(Error::SchemaMismatch {
+            expected: describe_schema(&expected),
+            found: describe_schema(&found),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Cast every column of `batch` to the type of the field of the same name in `target`.
+#[cfg(feature = "arrow")]
+fn cast_record_batch(batch: &RecordBatch, target: &SchemaRef) -> Result<RecordBatch, Error> {
+    let schema = batch.schema();
+    let columns = target
+        .fields()
+        .iter()
+        .map(|field| {
+            let index = schema.index_of(field.name())?;
+            arrow::compute::cast(batch.column(index), field.data_type())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let batch = RecordBatch::try_new(target.clone(), columns)?;
+    Ok(batch)
+}
+
 #[cfg(feature = "arrow")]
 pub type DefaultArrowStreamReader = ArrowStreamReader<Cursor<Vec<u8>>>;
 
+/// How far below `1.0` the progress of the last response of a stream can be, for the
+/// stream to be deemed complete. The progress is a float computed by the server.
+#[cfg(feature = "arrow")]
+const PROGRESS_TOLERANCE: f64 = f64::EPSILON * 16.;
+
+/// The [`DefaultArrowStreamReader`](DefaultArrowStreamReader) of
+/// [`RowsStreamReader::into_checked_arrow_reader`](RowsStreamReader::into_checked_arrow_reader),
+/// which also tells whether the stream was sent up to its end.
+#[cfg(feature = "arrow")]
+pub struct CheckedArrowStreamReader {
+    inner: DefaultArrowStreamReader,
+    completed_normally: bool,
+}
+
+#[cfg(feature = "arrow")]
+impl CheckedArrowStreamReader {
+    /// The schema of the batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+
+    /// Whether the server sent the stream up to its end. This is `false` when the stream
+    /// ended cleanly before the server reported having sent all of its rows (i.e. before
+    /// the progress of its last response reached `1.0`, give or take rounding errors), in
+    /// which case the stream should be opened again (e.g. with
+    /// [`ReadSession::stream_at`](crate::client::ReadSession::stream_at)) at the offset of
+    /// the rows read so far. Streams for which the server reports no
+    /// progress are deemed complete.
+    pub fn completed_normally(&self) -> bool {
+        self.completed_normally
+    }
+
+    /// The underlying Arrow [`StreamReader`](arrow::ipc::reader::StreamReader).
+    pub fn into_inner(self) -> DefaultArrowStreamReader {
+        self.inner
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl Iterator for CheckedArrowStreamReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl RecordBatchReader for CheckedArrowStreamReader {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+/// Reorder the columns of `batch` to follow the selected `fields`.
+#[cfg(feature = "arrow")]
+pub(crate) fn order_columns(batch: &RecordBatch, fields: &[String]) -> Result<RecordBatch, Error> {
+    let schema = batch.schema();
+    let mut indices = Vec::with_capacity(fields.len());
+    for field in fields {
+        let column = field.split('.').next().unwrap_or(field);
+        let index = schema.index_of(column)?;
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    Ok(batch.project(&indices)?)
+}
+
+/// Split a serialized (already stripped) IPC message into the metadata and body of a
+/// [`FlightData`](arrow_flight::FlightData), without copying it.
+#[cfg(feature = "flight")]
+fn flight_data(msg: Bytes) -> Result<FlightData, Error> {
+    let mut len = [0u8; 4];
+    len.copy_from_slice(
+        msg.get(0..4)
+            .ok_or(Error::invalid_arrow("arrow message of invalid len"))?,
+    );
+    let len = u32::from_le_bytes(len) as usize;
+    if msg.len() < 4 + len {
+        return Err(Error::invalid_arrow("truncated arrow message"));
+    }
+    Ok(FlightData::new()
+        .with_data_header(msg.slice(4..4 + len))
+        .with_data_body(msg.slice(4 + len..)))
+}
+
+/// Split `batch` into slices of at most `max_rows` rows. Slicing does not copy the data.
+#[cfg(feature = "arrow")]
+fn split_record_batch(batch: RecordBatch, max_rows: usize) -> Vec<RecordBatch> {
+    let num_rows = batch.num_rows();
+    if max_rows == 0 || num_rows <= max_rows {
+        return vec![batch];
+    }
+    (0..num_rows)
+        .step_by(max_rows)
+        .map(|offset| batch.slice(offset, max_rows.min(num_rows - offset)))
+        .collect()
+}
+
+/// Concatenate consecutive `batches` into one. The statistics are those of the last
+/// batch, with the row counts summed up.
+#[cfg(feature = "arrow")]
+fn concat_batches_with_stats(
+    mut batches: Vec<(RecordBatch, BatchStats)>,
+) -> Result<(RecordBatch, BatchStats), Error> {
+    if batches.len() == 1 {
+        return Ok(batches.remove(0));
+    }
+    let row_count = batches.iter().map(|(_, stats)| stats.row_count).sum();
+    let (last, last_stats) = &batches[batches.len() - 1];
+    let stats = BatchStats {
+        row_count,
+        ..last_stats.clone()
+    };
+    let batch = concat_batches(&last.schema(), batches.iter().map(|(batch, _)| batch))?;
+    Ok((batch, stats))
+}
+
+/// Give all the `batches` the same schema, starting from `schema`: a field is nullable if
+/// it is in any of the batches. Fails if batches have different fields or types.
+#[cfg(feature = "arrow")]
+pub(crate) fn unify_record_batches(
+    schema: SchemaRef,
+    batches: Vec<RecordBatch>,
+) -> Result<(SchemaRef, Vec<RecordBatch>), Error> {
+    let mut fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .map(|field| field.as_ref().clone())
+        .collect();
+    for batch in &batches {
+        let batch_schema = batch.schema();
+        let compatible = batch_schema.fields().len() == fields.len()
+            && batch_schema
+                .fields()
+                .iter()
+                .zip(&fields)
+                .all(|(found, expected)| {
+                    found.name() == expected.name()
+                        && found.data_type().equals_datatype(expected.data_type())
+                });
+        if !compatible {
+            return Err(Error::SchemaMismatch {
+                expected: describe_schema(&schema),
+                found: describe_schema(&batch_schema),
+            });
+        }
+        for (field, found) in fields.iter_mut().zip(batch_schema.fields()) {
+            if found.is_nullable() && !field.is_nullable() {
+                *field = field.clone().with_nullable(true);
+            }
+        }
+    }
+
+    let schema = Arc::new(ArrowSchemaRepr::new_with_metadata(
+        fields,
+        schema.metadata().clone(),
+    ));
+    let batches = batches
+        .into_iter()
+        .map(|batch| batch.with_schema(schema.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((schema, batches))
+}
+
+/// End `stream` once its batches hold at least `max_rows` rows, without waiting for the
+/// next one.
+#[cfg(feature = "arrow")]
+fn take_serialized_rows(
+    stream: BoxStream<'static, Result<(Vec<u8>, BatchStats), Error>>,
+    max_rows: usize,
+) -> BoxStream<'static, Result<(Vec<u8>, BatchStats), Error>> {
+    futures::stream::unfold((stream, max_rows), |(mut stream, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        let item = stream.next().await?;
+        let remaining = match &item {
+            Ok((_, stats)) => remaining.saturating_sub(stats.row_count.max(0) as usize),
+            Err(_) => remaining,
+        };
+        Some((item, (stream, remaining)))
+    })
+    .boxed()
+}
+
+/// Merge the consecutive batches of `stream` until they hold at least `min_rows` rows.
+/// If the stream fails, the batches merged so far are yielded before the error.
+#[cfg(feature = "arrow")]
+fn coalesce_record_batches(
+    stream: BoxStream<'static, Result<(RecordBatch, BatchStats), Error>>,
+    min_rows: usize,
+) -> BoxStream<'static, Result<(RecordBatch, BatchStats), Error>> {
+    let min_rows = min_rows.max(1);
+    futures::stream::unfold(
+        (stream, None, false),
+        move |(mut stream, pending_err, done)| async move {
+            if let Some(err) = pending_err {
+                return Some((Err(err), (stream, None, true)));
+            }
+            if done {
+                return None;
+            }
+
+            let mut buffer = Vec::new();
+            let mut rows = 0;
+            let mut err = None;
+            while rows < min_rows {
+                match stream.next().await {
+                    Some(Ok(batch)) => {
+                        rows += batch.0.num_rows();
+                        buffer.push(batch);
+                    }
+                    Some(Err(e)) => {
+                        err = Some(e);
+                        break;
+                    }
+                    None => break,
+                }
+            }
+
+            let done = rows < min_rows;
+            if buffer.is_empty() {
+                return err.map(|err| (Err(err), (stream, None, true)));
+            }
+            Some((concat_batches_with_stats(buffer), (stream, err, done)))
+        },
+    )
+    .boxed()
+}
+
+/// The rows of `batch`, as JSON objects.
+#[cfg(feature = "json")]
+fn json_rows(batch: &RecordBatch) -> Result<Vec<serde_json::Value>, Error> {
+    let mut writer = arrow::json::ArrayWriter::new(Vec::new());
+    writer.write(batch)?;
+    writer.finish()?;
+    let buf = writer.into_inner();
+    if buf.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_slice(&buf)?)
+}
+
+/// A stream of Arrow [`RecordBatch`](arrow::record_batch::RecordBatch), decoded
+/// as the data is received.
+#[cfg(feature = "arrow")]
+pub type RecordBatchStream = BoxStream<'static, Result<RecordBatch, Error>>;
+
+/// A stream of shared Arrow [`RecordBatch`](arrow::record_batch::RecordBatch), see
+/// [`RowsStreamReader::into_shared_stream`](RowsStreamReader::into_shared_stream).
+#[cfg(feature = "arrow")]
+pub type SharedRecordBatchStream = BoxStream<'static, Result<Arc<RecordBatch>, Error>>;
+
+/// A [`RecordBatchStream`](RecordBatchStream) which knows the schema of its batches
+/// before any of them is received. See
+/// [`RowsStreamReader::into_stream_with_schema`](RowsStreamReader::into_stream_with_schema).
+#[cfg(feature = "arrow")]
+pub struct RecordBatchStreamWithSchema {
+    schema: SchemaRef,
+    inner: RecordBatchStream,
+}
+
+#[cfg(feature = "arrow")]
+impl RecordBatchStreamWithSchema {
+    /// The schema of the batches of this stream.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// The underlying stream of batches.
+    pub fn into_inner(self) -> RecordBatchStream {
+        self.inner
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl futures::Stream for RecordBatchStreamWithSchema {
+    type Item = Result<RecordBatch, Error>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+/// Anything that can be turned into a [`RecordBatchStream`](RecordBatchStream), such as
+/// a [`RowsStreamReader`](RowsStreamReader).
+///
+/// Code consuming record batches can be made generic over this trait, so that it can be
+/// tested against a fake source (e.g. `futures::stream::iter` over canned batches)
+/// instead of a live BigQuery stream.
+#[cfg(feature = "arrow")]
+pub trait BatchSource {
+    /// Consume this source into a stream of record batches.
+    fn batches(self) -> Result<RecordBatchStream, Error>;
+}
+
+#[cfg(feature = "arrow")]
+impl BatchSource for RecordBatchStream {
+    fn batches(self) -> Result<RecordBatchStream, Error> {
+        Ok(self)
+    }
+}
+
+/// Statistics about a batch of rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStats {
+    /// When the batch was received by the client. The API does not report when batches
+    /// are produced server-side.
+    pub received_at: SystemTime,
+    /// The number of rows in the batch, as reported by the server.
+    pub row_count: i64,
+    /// How far into the stream the server was, before and after this batch.
+    pub progress: Option<Progress>,
+}
+
+/// Accounting shared by all the streams of a read session.
+#[derive(Debug, Default)]
+pub(crate) struct SessionStats {
+    bytes: AtomicU64,
+    uncompressed_bytes: AtomicU64,
+    max_bytes: Option<u64>,
+}
+
+impl SessionStats {
+    pub(crate) fn new(max_bytes: Option<u64>) -> Self {
+        Self {
+            max_bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Record a downloaded batch of `len` bytes, failing if that goes over the limit.
+    /// `uncompressed_len` is the size of the batch before compression, as reported by the
+    /// server, or zero if it is not compressed.
+    fn record_batch(&self, len: u64, uncompressed_len: u64) -> Result<(), Error> {
+        let uncompressed_len = if uncompressed_len > 0 {
+            uncompressed_len
+        } else {
+            len
+        };
+        self.uncompressed_bytes
+            .fetch_add(uncompressed_len, Ordering::Relaxed);
+
+        let total = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+        match self.max_bytes {
+            Some(limit) if total > limit => Err(Error::ByteLimitExceeded { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn uncompressed_bytes(&self) -> u64 {
+        self.uncompressed_bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// A predicate selecting the rows of a batch, see [`RowsStreamReader::filter_rows`].
+#[cfg(feature = "arrow")]
+type RowFilter = Arc<dyn Fn(&RecordBatch) -> BooleanArray + Send + Sync>;
+
+/// Options of a [`RowsStreamReader`](RowsStreamReader), which apply to the streams it
+/// is turned into.
+#[cfg(feature = "arrow")]
+#[derive(Default)]
+struct ReaderOptions {
+    known_schema: Option<SchemaRef>,
+    skip_empty_batches: bool,
+    field_order: Option<Vec<String>>,
+    max_batch_rows: Option<usize>,
+    min_batch_rows: Option<usize>,
+    flatten: Option<ListFlattening>,
+    strict_nonempty: bool,
+    filter: Option<RowFilter>,
+    max_rows: Option<usize>,
+    strip_schema_metadata: bool,
+}
+
+/// Options of [`RowsStreamReader::write_csv`](RowsStreamReader::write_csv).
+#[cfg(feature = "csv")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// The field delimiter. Defaults to `,`.
+    pub delimiter: u8,
+    /// Whether to start with a line of column names. Defaults to `true`.
+    pub header: bool,
+    /// How null values are written. Defaults to an empty field.
+    pub null: String,
+}
+
+#[cfg(feature = "csv")]
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            header: true,
+            null: String::new(),
+        }
+    }
+}
+
+/// What [`RowsStreamReader::flatten`](RowsStreamReader::flatten) does to list (i.e.
+/// `ARRAY`) columns.
+#[cfg(feature = "arrow")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFlattening {
+    /// Keep list columns as they are.
+    Keep,
+    /// Give each item of a list its own row, repeating the other columns, like a SQL
+    /// `LEFT JOIN UNNEST` would. Rows with a null or empty list are kept, with a null item.
+    /// Exploding several list columns yields every combination of their items.
+    Explode,
+}
+
+/// Re-opens a stream at the given offset.
+pub(crate) type Reopen = Box<
+    dyn Fn(i64) -> BoxFuture<'static, Result<Streaming<ReadRowsResponse>, Error>> + Send + Sync,
+>;
+
+/// How a [`RowsStreamReader`](RowsStreamReader) resumes its stream when the server asks
+/// it to.
+pub(crate) struct Resume {
+    pub(crate) reopen: Reopen,
+    /// The offset the stream was opened at.
+    pub(crate) offset: i64,
+    /// The maximum number of consecutive attempts at resuming the stream.
+    pub(crate) max_attempts: u32,
+    pub(crate) backoff: SharedBackoff,
+}
+
+/// Whether a stream failing with `status` can be resumed by opening it again. This is not
+/// the case of `FAILED_PRECONDITION` (e.g. for an expired session), which takes a new
+/// session rather than the same stream at the same offset.
+fn is_resumable(status: &Status) -> bool {
+    matches!(status.code(), Code::Aborted | Code::Unavailable)
+}
+
+/// The responses of a stream, starting with the `peeked` one (if any). If `resume` is
+/// set, the stream is opened again where it failed, as long as the failure is resumable.
+fn responses(
+    peeked: Option<ReadRowsResponse>,
+    upstream: Streaming<ReadRowsResponse>,
+    resume: Option<Resume>,
+) -> BoxStream<'static, Result<ReadRowsResponse, Error>> {
+    let resume = match resume {
+        Some(resume) => resume,
+        None => {
+            return futures::stream::iter(peeked.map(Ok))
+                .chain(upstream)
+                .map_err(Error::from)
+                .boxed()
+        }
+    };
+
+    let offset = resume.offset + peeked.as_ref().map(|resp| resp.row_count).unwrap_or(0);
+    let resumed = futures::stream::unfold(
+        (upstream, resume, offset),
+        |(mut upstream, resume, mut offset)| async move {
+            let mut attempts = 0;
+            loop {
+                let mut status = match upstream.next().await {
+                    Some(Ok(resp)) => {
+                        offset += resp.row_count;
+                        return Some((Ok(resp), (upstream, resume, offset)));
+                    }
+                    Some(Err(status)) => status,
+                    None => return None,
+                };
+                loop {
+                    if !is_resumable(&status) || attempts >= resume.max_attempts {
+                        return Some((Err(status.into()), (upstream, resume, offset)));
+                    }
+                    attempts += 1;
+                    match next_delay(&resume.backoff, attempts) {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => return Some((Err(status.into()), (upstream, resume, offset))),
+                    }
+                    match (resume.reopen)(offset).await {
+                        Ok(reopened) => {
+                            upstream = reopened;
+                            break;
+                        }
+                        Err(Error::Status(reopen_status)) => status = reopen_status,
+                        Err(err) => return Some((Err(err), (upstream, resume, offset))),
+                    }
+                }
+            }
+        },
+    );
+    futures::stream::iter(peeked.map(Ok)).chain(resumed).boxed()
+}
+
+/// Split `stream` into `n` streams which all yield every item, see
+/// [`RowsStreamReader::tee`](RowsStreamReader::tee).
+#[cfg(feature = "arrow")]
+fn fan_out<T: Clone + Send + 'static>(
+    stream: BoxStream<'static, Result<T, Error>>,
+    n: usize,
+    capacity: usize,
+) -> Vec<BoxStream<'static, Result<T, Error>>> {
+    let (txs, rxs): (Vec<_>, Vec<_>) = (0..n).map(|_| tokio::sync::mpsc::channel(capacity)).unzip();
+
+    // As with `into_buffered_stream`, stop as soon as all the consumers went away.
+    let consumers = txs.clone();
+    let mut stream = Box::pin(stream.take_until(async move {
+        futures::future::join_all(consumers.iter().map(|consumer| consumer.closed())).await
+    }));
+    tokio::spawn(async move {
+        let mut txs = txs;
+        while let Some(batch) = stream.next().await {
+            let batch = batch.map_err(Arc::new);
+            let mut sent = Vec::with_capacity(txs.len());
+            for tx in txs {
+                let item = batch.clone().map_err(|source| Error::Shared { source });
+                // Consumers which went away are not fed anymore.
+                if tx.send(item).await.is_ok() {
+                    sent.push(tx);
+                }
+            }
+            txs = sent;
+            if txs.is_empty() {
+                break;
+            }
+        }
+    });
+
+    rxs.into_iter()
+        .map(|rx| {
+            futures::stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|batch| (batch, rx))
+            })
+            .boxed()
+        })
+        .collect()
+}
+
 /// A wrapper around a [BigQuery Storage stream](https://cloud.google.com/bigquery/docs/reference/storage#read_from_a_session_stream).
+///
+/// Dropping a reader, or any of the streams derived from it, cancels the underlying
+/// `ReadRows` call (with an HTTP/2 `RST_STREAM`), even if it was not read to the end. For
+/// [`into_buffered_stream`](RowsStreamReader::into_buffered_stream) and
+/// [`tee`](RowsStreamReader::tee), the background task stops as soon as its consumers are
+/// gone.
 pub struct RowsStreamReader {
+    stream: String,
     schema: Schema,
     upstream: Streaming<ReadRowsResponse>,
+    /// A response already taken from `upstream`, e.g. by
+    /// [`peek_schema`](RowsStreamReader::peek_schema).
+    peeked: Option<ReadRowsResponse>,
+    resume: Option<Resume>,
+    stats: Arc<SessionStats>,
+    #[cfg(feature = "arrow")]
+    opts: ReaderOptions,
 }
 
 impl RowsStreamReader {
-    pub(crate) fn new(schema: Schema, upstream: Streaming<ReadRowsResponse>) -> Self {
-        Self { schema, upstream }
+    pub(crate) fn new(
+        stream: String,
+        schema: Schema,
+        upstream: Streaming<ReadRowsResponse>,
+        stats: Arc<SessionStats>,
+    ) -> Self {
+        Self {
+            stream,
+            schema,
+            upstream,
+            peeked: None,
+            resume: None,
+            stats,
+            #[cfg(feature = "arrow")]
+            opts: ReaderOptions::default(),
+        }
     }
 
-    /// Consume the entire stream into an Arrow [StreamReader](arrow::ipc::reader::StreamReader).
-    #[cfg(feature = "arrow")]
-    pub async fn into_arrow_reader(self) -> Result<DefaultArrowStreamReader, Error> {
-        let mut serialized_arrow_stream = self
-            .upstream
-            .map_err(|e| e.into())
-            .and_then(|resp| {
-                let ReadRowsResponse { rows, .. } = resp;
-                let out =
-                    rows.ok_or(Error::invalid("no rows received"))
-                        .and_then(|rows| match rows {
-                            Rows::ArrowRecordBatch(ArrowRecordBatch {
-                                serialized_record_batch,
-                                ..
-                            }) => Ok(serialized_record_batch),
-                            _ => {
-                                let err = Error::invalid("expected arrow record batch");
-                                Err(err)
-                            }
-                        });
+    /// A reader for a stream of a session sent without a schema, which then has to come
+    /// along the first response of the stream. That response is kept for the batches to
+    /// be read later on.
+    pub(crate) async fn with_schema_from_rows(
+        stream: String,
+        mut upstream: Streaming<ReadRowsResponse>,
+        stats: Arc<SessionStats>,
+    ) -> Result<Self, Error> {
+        let peeked = upstream.next().await.transpose()?;
+        let schema = match peeked.as_ref().and_then(|resp| resp.schema.clone()) {
+            Some(RowsSchema::ArrowSchema(arrow_schema)) => Schema::ArrowSchema(arrow_schema),
+            Some(RowsSchema::AvroSchema(avro_schema)) => Schema::AvroSchema(avro_schema),
+            None => return Err(Error::MissingSchema {}),
+        };
+        let mut reader = Self::new(stream, schema, upstream, stats);
+        reader.peeked = peeked;
+        Ok(reader)
+    }
+
+    /// Resume the stream with `resume` when it fails with a resumable status.
+    pub(crate) fn with_resume(mut self, resume: Resume) -> Self {
+        self.resume = Some(resume);
+        self
+    }
+
+    /// The name of the underlying stream.
+    pub fn stream_name(&self) -> &str {
+        &self.stream
+    }
+
+    /// Consume the stream into the responses of the server, as they are received. Rows are
+    /// left serialized (and compressed, if so requested), which is how they can be read
+    /// without the `arrow` feature, e.g. to decode them with another library. The size of
+    /// the rows still counts towards [`bytes_downloaded`](crate::client::ReadSession::bytes_downloaded)
+    /// and [`max_bytes`](crate::client::ReadSessionBuilder::max_bytes).
+    pub fn into_raw_responses(self) -> BoxStream<'static, Result<ReadRowsResponse, Error>> {
+        let stats = self.stats;
+        responses(self.peeked, self.upstream, self.resume)
+            .and_then(move |resp| {
+                let len = match &resp.rows {
+                    Some(Rows::ArrowRecordBatch(ArrowRecordBatch {
+                        serialized_record_batch,
+                        ..
+                    })) => serialized_record_batch.len(),
+                    Some(Rows::AvroRows(avro_rows)) => avro_rows.serialized_binary_rows.len(),
+                    None => 0,
+                };
+                let uncompressed_len = resp.uncompressed_byte_size.unwrap_or_default().max(0);
+                let out = stats
+                    .record_batch(len as u64, uncompressed_len as u64)
+                    .map(|_| resp);
                 ready(out)
             })
+            .boxed()
+    }
+
+    /// Decode the batches of this stream with `schema`, instead of deserializing the
+    /// schema of the read session. This saves some work for hot loops reading the same
+    /// table over and over. The schema of the session is only looked at if a batch
+    /// fails to decode, in which case a mismatch is reported as
+    /// [`Error::SchemaMismatch`](crate::Error::SchemaMismatch).
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn with_known_schema(mut self, schema: SchemaRef) -> Self {
+        self.opts.known_schema = Some(schema);
+        self
+    }
+
+    /// Whether to drop batches with no rows, which the server sometimes sends to report
+    /// progress. Defaults to `false`, i.e. all batches are yielded.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn skip_empty_batches(mut self, skip: bool) -> Self {
+        self.opts.skip_empty_batches = skip;
+        self
+    }
+
+    /// Reorder the columns of the batches to follow `fields`, which are field names as
+    /// given to [`selected_fields`](crate::client::ReadSessionBuilder::selected_fields).
+    /// Nested fields (e.g. `a.b`) are placed at the position of their top-level column.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn with_field_order(mut self, fields: Vec<String>) -> Self {
+        self.opts.field_order = Some(fields);
+        self
+    }
+
+    /// Split the batches sent by the server into batches of at most `rows` rows. The API
+    /// offers no way to choose the size of the batches, which can be large; this bounds
+    /// how many rows downstream consumers are handed at once. The slices share the memory
+    /// of the original batch, so this does not lower the peak memory of the reader itself.
+    /// Each slice comes with the [`BatchStats`](BatchStats) of the batch it was cut from.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn max_batch_rows(mut self, rows: usize) -> Self {
+        self.opts.max_batch_rows = Some(rows);
+        self
+    }
+
+    /// Merge the consecutive batches sent by the server until they hold at least `rows`
+    /// rows, for consumers with a high cost per batch. The last batch holds whatever is
+    /// left at the end of the stream. Merged batches come with the
+    /// [`BatchStats`](BatchStats) of the last batch they contain, with the row counts
+    /// summed up.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn coalesce_batches(mut self, rows: usize) -> Self {
+        self.opts.min_batch_rows = Some(rows);
+        self
+    }
+
+    /// Flatten nested and repeated columns, for consumers which only handle scalar columns
+    /// (e.g. CSV files or relational databases). The fields of `RECORD` columns become
+    /// columns of their own, named after their path (e.g. `address.city`), and `ARRAY`
+    /// columns are handled according to `lists`.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn flatten(mut self, lists: ListFlattening) -> Self {
+        self.opts.flatten = Some(lists);
+        self
+    }
+
+    /// Only yield the rows for which `predicate` is true (null counting as false), e.g.
+    /// for conditions a [`row_restriction`](crate::client::ReadSessionBuilder::row_restriction)
+    /// cannot express. `predicate` is given each batch, once
+    /// [`flatten`](RowsStreamReader::flatten)ed if so requested.
+    ///
+    /// Rows are filtered once downloaded: this does not save any egress, so filter on the
+    /// server as much as possible. Filtering can leave batches small or empty, see
+    /// [`coalesce_batches`](RowsStreamReader::coalesce_batches) and
+    /// [`skip_empty_batches`](RowsStreamReader::skip_empty_batches).
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn filter_rows<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&RecordBatch) -> BooleanArray + Send + Sync + 'static,
+    {
+        self.opts.filter = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Stop after `rows` rows of the stream, e.g. to split a large stream between workers,
+    /// each reading a window of it (see [`ReadSession::stream_window`](crate::client::ReadSession::stream_window)).
+    /// The stream is not read any further once these rows are received.
+    ///
+    /// Rows are counted as sent by the server, before any
+    /// [`filter_rows`](RowsStreamReader::filter_rows) or
+    /// [`flatten`](RowsStreamReader::flatten). This only applies to
+    /// [`into_stream`](RowsStreamReader::into_stream) and the streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn limit_rows(mut self, rows: usize) -> Self {
+        self.opts.max_rows = Some(rows);
+        self
+    }
+
+    /// Fail with [`Error::EmptyStream`](crate::Error::EmptyStream) once the stream is
+    /// exhausted if it returned no rows at all, rather than just ending.
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn strict_nonempty(mut self) -> Self {
+        self.opts.strict_nonempty = true;
+        self
+    }
+
+    /// Drop the metadata of the schema of the batches and of its fields, which the consumer
+    /// may not need, e.g. to save memory on wide tables. This does not apply to a schema
+    /// given to [`with_known_schema`](RowsStreamReader::with_known_schema).
+    ///
+    /// This only applies to [`into_stream`](RowsStreamReader::into_stream) and the
+    /// streams derived from it.
+    #[cfg(feature = "arrow")]
+    pub fn strip_schema_metadata(mut self) -> Self {
+        self.opts.strip_schema_metadata = true;
+        self
+    }
+
+    /// The schema of the rows actually sent by the server, which may differ from the
+    /// schema of the session in subtle ways (e.g. field metadata). This waits for the
+    /// first response of the stream, which is kept for the batches to be read later on.
+    /// If the server did not send a schema along the rows, this is the schema of the
+    /// session.
+    #[cfg(feature = "arrow")]
+    pub async fn peek_schema(&mut self) -> Result<SchemaRef, Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.upstream.next().await.transpose()?;
+        }
+        let serialized_schema = match &self.peeked {
+            Some(ReadRowsResponse {
+                schema: Some(RowsSchema::ArrowSchema(ArrowSchema { serialized_schema })),
+                ..
+            }) => serialized_schema,
+            _ => match &self.schema {
+                Schema::ArrowSchema(ArrowSchema { serialized_schema }) => serialized_schema,
+                Schema::AvroSchema(_) => {
+                    return Err(Error::UnsupportedDataFormat { format: "Avro" })
+                }
+            },
+        };
+        decode_schema(strip_continuation_bytes(serialized_schema)?)
+    }
+
+    /// Consume the stream into its rows, as JSON objects. Nested and repeated columns
+    /// become nested objects and arrays, and null values are omitted. This is much slower
+    /// than working on record batches, but handy for debugging, small exports, or
+    /// JSON-only consumers.
+    #[cfg(feature = "json")]
+    pub fn into_json_stream(
+        self,
+    ) -> Result<BoxStream<'static, Result<serde_json::Value, Error>>, Error> {
+        let stream = self
+            .into_stream()?
+            .and_then(|batch| ready(json_rows(&batch)))
+            .map_ok(|rows| futures::stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed();
+        Ok(stream)
+    }
+
+    /// Consume the stream into CSV, written to `w` batch by batch, so that memory use does
+    /// not grow with the size of the stream. Timestamps are written in RFC 3339 and
+    /// decimals in full.
+    ///
+    /// CSV has no notion of nested values: tables with `RECORD` or `ARRAY` columns have to
+    /// be [`flatten`](RowsStreamReader::flatten)ed, exploding lists, or this fails.
+    ///
+    /// Writing to `w` blocks, so `w` is best kept to in-memory buffers or local files.
+    #[cfg(feature = "csv")]
+    pub async fn write_csv<W: std::io::Write>(self, w: W, opts: CsvOptions) -> Result<(), Error> {
+        let mut writer = arrow::csv::WriterBuilder::new()
+            .with_delimiter(opts.delimiter)
+            .with_header(opts.header)
+            .with_null(opts.null)
+            .build(w);
+        let mut stream = self.into_stream()?;
+        while let Some(batch) = stream.next().await {
+            writer.write(&batch?)?;
+        }
+        Ok(())
+    }
+
+    /// Consume the stream into Arrow Flight messages: the schema of the session first,
+    /// then one message per batch of rows. The messages are made from the serialized
+    /// rows as sent by the server, without decoding them, e.g. to re-serve them from an
+    /// Arrow Flight server.
+    ///
+    /// As the rows are not decoded, the options of this reader (e.g.
+    /// [`with_field_order`](RowsStreamReader::with_field_order)) do not apply.
+    #[cfg(feature = "flight")]
+    pub fn into_flight_data(self) -> Result<BoxStream<'static, Result<FlightData, Error>>, Error> {
+        let (serialized_schema, serialized_arrow_stream) = self.into_serialized_parts()?;
+        let schema = flight_data(Bytes::from(serialized_schema))?;
+        let batches = serialized_arrow_stream.and_then(|(msg, _)| {
+            let msg = Bytes::from(msg);
+            let out = strip_continuation_bytes(&msg).and_then(|_| flight_data(msg.slice(4..)));
+            ready(out)
+        });
+        let stream = futures::stream::once(ready(Ok(schema)))
+            .chain(batches)
             .boxed();
+        Ok(stream)
+    }
 
+    #[cfg(feature = "arrow")]
+    #[allow(clippy::type_complexity)]
+    fn into_serialized_parts(
+        self,
+    ) -> Result<
+        (
+            Vec<u8>,
+            BoxStream<'static, Result<(Vec<u8>, BatchStats), Error>>,
+        ),
+        Error,
+    > {
         let serialized_schema = match self.schema {
             Schema::ArrowSchema(ArrowSchema { serialized_schema }) => serialized_schema,
-            _ => return Err(Error::invalid("expected arrow schema")),
+            Schema::AvroSchema(_) => return Err(Error::UnsupportedDataFormat { format: "Avro" }),
         };
+        let serialized_schema = strip_continuation_bytes(serialized_schema.as_slice())?.to_vec();
+
+        let session_schema = serialized_schema.clone();
+        let stats = self.stats;
+        let serialized_arrow_stream = responses(self.peeked, self.upstream, self.resume)
+            .and_then(move |resp| {
+                let received_at = SystemTime::now();
+                let ReadRowsResponse {
+                    rows,
+                    schema,
+                    uncompressed_byte_size,
+                    row_count,
+                    stats: stream_stats,
+                    ..
+                } = resp;
+                let batch_stats = BatchStats {
+                    received_at,
+                    row_count,
+                    progress: stream_stats.and_then(|stream_stats| stream_stats.progress),
+                };
+                let uncompressed_byte_size = uncompressed_byte_size.unwrap_or_default().max(0);
+                if let Some(RowsSchema::ArrowSchema(ArrowSchema { serialized_schema })) = schema {
+                    if let Err(err) = check_schema(&session_schema, &serialized_schema) {
+                        return ready(Err(err));
+                    }
+                }
+                let out = rows
+                    .ok_or(Error::MissingRows {})
+                    .and_then(|rows| match rows {
+                        Rows::ArrowRecordBatch(ArrowRecordBatch {
+                            serialized_record_batch,
+                            ..
+                        }) => Ok(serialized_record_batch),
+                        _ => {
+                            let err = Error::UnsupportedDataFormat { format: "Avro" };
+                            Err(err)
+                        }
+                    })
+                    .and_then(|serialized_record_batch| {
+                        stats.record_batch(
+                            serialized_record_batch.len() as u64,
+                            uncompressed_byte_size as u64,
+                        )?;
+                        decompress_rows(serialized_record_batch, uncompressed_byte_size)
+                    })
+                    .map(|serialized_record_batch| (serialized_record_batch, batch_stats));
+                ready(out)
+            })
+            .boxed();
 
-        let mut buf = Vec::new();
-        buf.extend(strip_continuation_bytes(serialized_schema.as_slice())?);
+        Ok((serialized_schema, serialized_arrow_stream))
+    }
 
+    /// Consume the entire stream into an Arrow [StreamReader](arrow::ipc::reader::StreamReader).
+    #[cfg(feature = "arrow")]
+    pub async fn into_arrow_reader(self) -> Result<DefaultArrowStreamReader, Error> {
+        Ok(self.into_checked_arrow_reader().await?.into_inner())
+    }
+
+    /// Like [`into_arrow_reader`](RowsStreamReader::into_arrow_reader), but the reader
+    /// also tells whether the server closed the stream early, see
+    /// [`completed_normally`](CheckedArrowStreamReader::completed_normally).
+    #[cfg(feature = "arrow")]
+    pub async fn into_checked_arrow_reader(self) -> Result<CheckedArrowStreamReader, Error> {
+        let (serialized_schema, mut serialized_arrow_stream) = self.into_serialized_parts()?;
+
+        let mut buf = serialized_schema;
+
+        let mut completed_normally = true;
         while let Some(msg) = serialized_arrow_stream.next().await {
-            let msg = msg?;
+            let (msg, stats) = msg?;
+            if let Some(progress) = stats.progress {
+                completed_normally = progress.at_response_end >= 1. - PROGRESS_TOLERANCE;
+            }
             let body = strip_continuation_bytes(msg.as_slice())?;
             buf.extend(body);
         }
@@ -83,8 +1200,269 @@ impl RowsStreamReader {
         // of the stream. Gotta give the people what they want.
         buf.extend(&[0u8; 4]);
 
-        let reader = ArrowStreamReader::try_new(Cursor::new(buf))?;
+        let inner = ArrowStreamReader::try_new_unbuffered(Cursor::new(buf), None)?;
 
-        Ok(reader)
+        Ok(CheckedArrowStreamReader {
+            inner,
+            completed_normally,
+        })
+    }
+
+    /// Turn this reader into a [`Stream`](futures::stream::Stream) of Arrow
+    /// [`RecordBatch`](arrow::record_batch::RecordBatch). Unlike
+    /// [`into_arrow_reader`](RowsStreamReader::into_arrow_reader), each batch is decoded
+    /// and yielded as soon as it is received.
+    #[cfg(feature = "arrow")]
+    pub fn into_stream(self) -> Result<RecordBatchStream, Error> {
+        let stream = self
+            .into_stream_with_stats()?
+            .map_ok(|(batch, _)| batch)
+            .boxed();
+        Ok(stream)
+    }
+
+    /// Like [`into_stream`](RowsStreamReader::into_stream), but each batch comes along with
+    /// [`BatchStats`](BatchStats), e.g. to monitor how far behind a reader is.
+    #[cfg(feature = "arrow")]
+    pub fn into_stream_with_stats(
+        self,
+    ) -> Result<BoxStream<'static, Result<(RecordBatch, BatchStats), Error>>, Error> {
+        let known_schema = self.opts.known_schema.clone();
+        let skip_empty_batches = self.opts.skip_empty_batches;
+        let field_order = self.opts.field_order.clone();
+        let max_batch_rows = self.opts.max_batch_rows;
+        let min_batch_rows = self.opts.min_batch_rows;
+        let flatten = self.opts.flatten;
+        let filter = self.opts.filter.clone();
+        let mut remaining_rows = self.opts.max_rows;
+        let stream_name = self.stream.clone();
+        let nonempty_stream_name = self.opts.strict_nonempty.then(|| stream_name.clone());
+        let strip_metadata = self.opts.strip_schema_metadata;
+        let (serialized_schema, serialized_arrow_stream) = self.into_serialized_parts()?;
+
+        let decoder = match known_schema {
+            Some(schema) => BatchDecoder::with_known_schema(schema, serialized_schema),
+            None => {
+                let mut decoder = BatchDecoder::new(&serialized_schema)?;
+                if strip_metadata {
+                    // Batches are decoded with the stripped schema, at no extra cost
+                    decoder.schema = strip_schema_metadata(&decoder.schema);
+                }
+                decoder
+            }
+        };
+
+        let serialized_arrow_stream = match remaining_rows {
+            Some(max_rows) => take_serialized_rows(serialized_arrow_stream, max_rows),
+            None => serialized_arrow_stream,
+        };
+
+        let mut batch_index = 0;
+        let mut offset = 0;
+        let stream = serialized_arrow_stream
+            .and_then(move |(msg, stats)| {
+                let batch = decoder
+                    .decode(&msg)
+                    .map(|batch| match &mut remaining_rows {
+                        Some(remaining) => {
+                            let rows = batch.num_rows().min(*remaining);
+                            *remaining -= rows;
+                            batch.slice(0, rows)
+                        }
+                        None => batch,
+                    })
+                    .and_then(|batch| match &field_order {
+                        Some(fields) => order_columns(&batch, fields),
+                        None => Ok(batch),
+                    })
+                    .and_then(|batch| match flatten {
+                        Some(lists) => flatten_record_batch(&batch, lists),
+                        None => Ok(batch),
+                    })
+                    .and_then(|batch| match &filter {
+                        Some(predicate) => Ok(filter_record_batch(&batch, &predicate(&batch))?),
+                        None => Ok(batch),
+                    })
+                    .map(|batch| (batch, stats))
+                    .map_err(|source| Error::BatchDecode {
+                        stream: stream_name.clone(),
+                        batch_index,
+                        offset,
+                        source: Box::new(source),
+                    });
+                batch_index += 1;
+                offset += msg.len() as u64;
+                ready(batch)
+            })
+            .map_ok(move |(batch, stats)| {
+                let batches = match max_batch_rows {
+                    Some(max_rows) => split_record_batch(batch, max_rows),
+                    None => vec![batch],
+                };
+                let batches = batches
+                    .into_iter()
+                    .map(move |batch| Ok((batch, stats.clone())));
+                futures::stream::iter(batches)
+            })
+            .try_flatten()
+            .try_filter(move |(batch, _)| ready(!skip_empty_batches || batch.num_rows() > 0))
+            .boxed();
+
+        let stream = if let Some(stream_name) = nonempty_stream_name {
+            let has_rows = Arc::new(AtomicBool::new(false));
+            let seen_rows = has_rows.clone();
+            let empty = futures::stream::once(async move {
+                if has_rows.load(Ordering::Relaxed) {
+                    None
+                } else {
+                    Some(Err(Error::EmptyStream {
+                        stream: stream_name,
+                    }))
+                }
+            })
+            .filter_map(ready);
+            stream
+                .inspect_ok(move |(batch, _)| {
+                    if batch.num_rows() > 0 {
+                        seen_rows.store(true, Ordering::Relaxed);
+                    }
+                })
+                .chain(empty)
+                .boxed()
+        } else {
+            stream
+        };
+
+        let stream = match min_batch_rows {
+            Some(min_rows) => coalesce_record_batches(stream, min_rows),
+            None => stream,
+        };
+
+        Ok(stream)
+    }
+
+    /// Like [`into_stream`](RowsStreamReader::into_stream), but the stream knows the schema
+    /// of its batches up front, derived from the schema of the session and the options of
+    /// this reader (e.g. [`flatten`](RowsStreamReader::flatten)), without waiting for a
+    /// batch.
+    #[cfg(feature = "arrow")]
+    pub fn into_stream_with_schema(self) -> Result<RecordBatchStreamWithSchema, Error> {
+        let schema = self.output_schema()?;
+        let inner = self.into_stream()?;
+        Ok(RecordBatchStreamWithSchema { schema, inner })
+    }
+
+    /// The schema of the batches of [`into_stream`](RowsStreamReader::into_stream), i.e.
+    /// the schema of the session with the options of this reader applied.
+    #[cfg(feature = "arrow")]
+    fn output_schema(&self) -> Result<SchemaRef, Error> {
+        let schema = match (&self.opts.known_schema, &self.schema) {
+            (Some(schema), _) => schema.clone(),
+            (None, Schema::ArrowSchema(ArrowSchema { serialized_schema })) => {
+                let schema = decode_schema(strip_continuation_bytes(serialized_schema)?)?;
+                if self.opts.strip_schema_metadata {
+                    strip_schema_metadata(&schema)
+                } else {
+                    schema
+                }
+            }
+            (None, Schema::AvroSchema(_)) => {
+                return Err(Error::UnsupportedDataFormat { format: "Avro" })
+            }
+        };
+        let mut batch = RecordBatch::new_empty(schema);
+        if let Some(fields) = &self.opts.field_order {
+            batch = order_columns(&batch, fields)?;
+        }
+        if let Some(lists) = self.opts.flatten {
+            batch = flatten_record_batch(&batch, lists)?;
+        }
+        Ok(batch.schema())
+    }
+
+    /// Like [`into_stream`](RowsStreamReader::into_stream), but batches are downloaded and
+    /// decoded ahead of time by a background task, holding at most `capacity` of them in
+    /// memory. This keeps the network busy while the consumer is processing a batch.
+    ///
+    /// This must be called from within a Tokio runtime. Panics if `capacity` is zero.
+    #[cfg(feature = "arrow")]
+    pub fn into_buffered_stream(self, capacity: usize) -> Result<RecordBatchStream, Error> {
+        let stream = self.into_stream()?;
+        let (tx, rx) = tokio::sync::mpsc::channel(capacity);
+
+        // Stop as soon as the consumer goes away, rather than when the next batch comes
+        // in, so that the underlying call is cancelled right away.
+        let consumer = tx.clone();
+        let mut stream = Box::pin(stream.take_until(async move { consumer.closed().await }));
+        tokio::spawn(async move {
+            while let Some(batch) = stream.next().await {
+                if tx.send(batch).await.is_err() {
+                    // The consumer went away, no need to keep downloading.
+                    break;
+                }
+            }
+        });
+
+        let stream = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|batch| (batch, rx))
+        })
+        .boxed();
+
+        Ok(stream)
+    }
+
+    /// Split this stream into `n` streams which all yield every batch, e.g. to write the
+    /// rows to several destinations while downloading them once. Batches are cheap to
+    /// clone, since their buffers are shared.
+    ///
+    /// Batches are downloaded by a background task, which holds at most `capacity` of them
+    /// in memory for each stream: the download goes at the pace of the slowest stream.
+    /// Streams which are dropped are no longer fed. An error is yielded by all the
+    /// streams, as an [`Error::Shared`](crate::Error::Shared).
+    ///
+    /// This must be called from within a Tokio runtime. Panics if `capacity` is zero.
+    #[cfg(feature = "arrow")]
+    pub fn tee(self, n: usize, capacity: usize) -> Result<Vec<RecordBatchStream>, Error> {
+        Ok(fan_out(self.into_stream()?, n, capacity))
+    }
+
+    /// Like [`tee`](RowsStreamReader::tee), but the streams yield the same
+    /// [`Arc`](std::sync::Arc)s, as with [`into_shared_stream`](RowsStreamReader::into_shared_stream),
+    /// rather than a clone of every batch for every stream.
+    #[cfg(feature = "arrow")]
+    pub fn tee_shared(
+        self,
+        n: usize,
+        capacity: usize,
+    ) -> Result<Vec<SharedRecordBatchStream>, Error> {
+        Ok(fan_out(self.into_shared_stream()?, n, capacity))
+    }
+
+    /// Like [`into_stream`](RowsStreamReader::into_stream), but batches are wrapped in an
+    /// [`Arc`](std::sync::Arc), so that handing them over to several consumers only
+    /// clones a pointer.
+    #[cfg(feature = "arrow")]
+    pub fn into_shared_stream(self) -> Result<SharedRecordBatchStream, Error> {
+        Ok(self.into_stream()?.map_ok(Arc::new).boxed())
+    }
+
+    /// Like [`into_stream`](RowsStreamReader::into_stream), but every batch is cast to the
+    /// `target` schema (with [`arrow::compute::cast`](arrow::compute::cast)). Columns are
+    /// matched by name, and columns that are not in `target` are dropped. Yields an error
+    /// when a column is missing or cannot be cast to the desired type.
+    #[cfg(feature = "arrow")]
+    pub fn into_casted_stream(self, target: SchemaRef) -> Result<RecordBatchStream, Error> {
+        let stream = self
+            .into_stream()?
+            .and_then(move |batch| ready(cast_record_batch(&batch, &target)))
+            .boxed();
+        Ok(stream)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl BatchSource for RowsStreamReader {
+    fn batches(self) -> Result<RecordBatchStream, Error> {
+        self.into_stream()
     }
 }
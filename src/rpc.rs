@@ -0,0 +1,149 @@
+//! The parts of the `google.rpc` error model the API attaches to its errors, in the
+//! details of a [`Status`](tonic::Status).
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use prost::Message;
+use tonic::metadata::KeyAndValueRef;
+use tonic::Status;
+
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+const DEBUG_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.DebugInfo";
+
+/// `google.rpc.Status`, as serialized in the details of a [`Status`](tonic::Status).
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// `google.rpc.RetryInfo`.
+#[derive(Clone, PartialEq, Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<prost_types::Duration>,
+}
+
+/// `google.rpc.DebugInfo`, which the server may attach to an error, see
+/// [`Error::debug_info`](crate::Error::debug_info).
+#[derive(Clone, PartialEq, Message)]
+pub struct DebugInfo {
+    /// The stack trace of the error on the server side.
+    #[prost(string, repeated, tag = "1")]
+    pub stack_entries: Vec<String>,
+    /// Any other debugging data.
+    #[prost(string, tag = "2")]
+    pub detail: String,
+}
+
+/// The details attached to `status`, which are empty if they cannot be decoded.
+fn details(status: &Status) -> Vec<prost_types::Any> {
+    RpcStatus::decode(status.details())
+        .map(|rpc_status| rpc_status.details)
+        .unwrap_or_default()
+}
+
+/// How long the server asks to wait before retrying, if it does.
+pub(crate) fn retry_delay(status: &Status) -> Option<Duration> {
+    let retry_info = details(status)
+        .into_iter()
+        .find(|detail| detail.type_url == RETRY_INFO_TYPE_URL)?;
+    let retry_delay = RetryInfo::decode(retry_info.value.as_slice())
+        .ok()?
+        .retry_delay?;
+    let seconds = u64::try_from(retry_delay.seconds).ok()?;
+    let nanos = u32::try_from(retry_delay.nanos).ok()?;
+    Some(Duration::new(seconds, nanos))
+}
+
+/// The debugging information attached to `status`, if any.
+pub(crate) fn debug_info(status: &Status) -> Option<DebugInfo> {
+    let debug_info = details(status)
+        .into_iter()
+        .find(|detail| detail.type_url == DEBUG_INFO_TYPE_URL)?;
+    DebugInfo::decode(debug_info.value.as_slice()).ok()
+}
+
+/// The text metadata sent along `status`, i.e. the trailers of the call but the ones
+/// making up the status itself.
+pub(crate) fn metadata(status: &Status) -> Vec<(String, String)> {
+    status
+        .metadata()
+        .iter()
+        .filter_map(|entry| match entry {
+            KeyAndValueRef::Ascii(key, value) => {
+                Some((key.as_str().to_string(), value.to_str().ok()?.to_string()))
+            }
+            KeyAndValueRef::Binary(_, _) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tonic::Code;
+
+    #[test]
+    fn decode_retry_delay() {
+        let retry_info = RetryInfo {
+            retry_delay: Some(prost_types::Duration {
+                seconds: 2,
+                nanos: 500_000_000,
+            }),
+        };
+        let mut value = Vec::new();
+        retry_info.encode(&mut value).unwrap();
+        let rpc_status = RpcStatus {
+            code: Code::ResourceExhausted as i32,
+            message: "quota exceeded".to_string(),
+            details: vec![prost_types::Any {
+                type_url: RETRY_INFO_TYPE_URL.to_string(),
+                value,
+            }],
+        };
+        let mut details = Vec::new();
+        rpc_status.encode(&mut details).unwrap();
+        let status =
+            Status::with_details(Code::ResourceExhausted, "quota exceeded", details.into());
+        assert_eq!(retry_delay(&status), Some(Duration::from_millis(2500)));
+
+        let status = Status::resource_exhausted("quota exceeded");
+        assert_eq!(retry_delay(&status), None);
+    }
+
+    #[test]
+    fn decode_debug_info_and_metadata() {
+        let expected = DebugInfo {
+            stack_entries: vec!["at ReadRows".to_string()],
+            detail: "debug id 1234".to_string(),
+        };
+        let mut value = Vec::new();
+        expected.encode(&mut value).unwrap();
+        let rpc_status = RpcStatus {
+            code: Code::Internal as i32,
+            message: "internal error".to_string(),
+            details: vec![prost_types::Any {
+                type_url: DEBUG_INFO_TYPE_URL.to_string(),
+                value,
+            }],
+        };
+        let mut details = Vec::new();
+        rpc_status.encode(&mut details).unwrap();
+        let mut status = Status::with_details(Code::Internal, "internal error", details.into());
+        status
+            .metadata_mut()
+            .insert("x-request-id", "abc".parse().unwrap());
+
+        assert_eq!(debug_info(&status), Some(expected));
+        assert_eq!(
+            metadata(&status),
+            vec![("x-request-id".to_string(), "abc".to_string())]
+        );
+    }
+}
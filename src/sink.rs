@@ -0,0 +1,245 @@
+//! Destinations of the record batches of a read session, e.g. a table format writer.
+#[cfg(feature = "parquet")]
+use std::fs::File;
+#[cfg(feature = "parquet")]
+use std::io::Write;
+#[cfg(feature = "parquet")]
+use std::path::PathBuf;
+#[cfg(feature = "parquet")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+
+use crate::Error;
+
+/// A destination for record batches, written one at a time, e.g. a Parquet file or a
+/// Delta Lake or Iceberg table. See [`ReadSession::drain_into`](crate::ReadSession::drain_into).
+#[tonic::async_trait]
+pub trait RecordBatchSink: Send + Sized {
+    /// Write `batch`. The next batch is not read before this returns, so slow sinks slow
+    /// the read down rather than buffering batches.
+    async fn write(&mut self, batch: RecordBatch) -> Result<(), Error>;
+
+    /// Complete the writes, e.g. to write the footer of a file or commit a transaction.
+    /// This is not called if reading or writing failed.
+    async fn finish(self) -> Result<(), Error>;
+
+    /// Clean up after reading or writing failed, e.g. to remove an incomplete file or roll
+    /// back a transaction. This is called instead of [`finish`](RecordBatchSink::finish).
+    /// Does nothing by default.
+    async fn abort(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// A [`RecordBatchSink`](RecordBatchSink) writing a Parquet file.
+///
+/// Writes are blocking, so `W` is best kept to local files or in-memory buffers.
+#[cfg(feature = "parquet")]
+pub struct ParquetSink<W: std::io::Write + Send> {
+    writer: parquet::arrow::ArrowWriter<W>,
+}
+
+#[cfg(feature = "parquet")]
+impl<W: std::io::Write + Send> ParquetSink<W> {
+    /// Write batches of the given `schema` (e.g. [`ReadSession::arrow_schema`](crate::ReadSession::arrow_schema))
+    /// into `w`, with the writer `props` or the defaults of the `parquet` crate.
+    pub fn try_new(
+        w: W,
+        schema: arrow::datatypes::SchemaRef,
+        props: Option<parquet::file::properties::WriterProperties>,
+    ) -> Result<Self, Error> {
+        let writer = parquet::arrow::ArrowWriter::try_new(w, schema, props)?;
+        Ok(Self { writer })
+    }
+}
+
+#[cfg(feature = "parquet")]
+#[tonic::async_trait]
+impl<W: std::io::Write + Send> RecordBatchSink for ParquetSink<W> {
+    async fn write(&mut self, batch: RecordBatch) -> Result<(), Error> {
+        self.writer.write(&batch)?;
+        Ok(())
+    }
+
+    async fn finish(self) -> Result<(), Error> {
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// A [`RecordBatchSink`](RecordBatchSink) writing Parquet files of at most
+/// `rows_per_file` rows each, e.g. for the files of a data lake. Files are named after
+/// `path_template`, in which `{}` is replaced by the index of the file, zero-padded to 4
+/// digits: `out/part-{}.parquet` gives `out/part-0000.parquet`, `out/part-0001.parquet`
+/// and so on.
+///
+/// A file is only created once there are rows to write into it, so that no file is
+/// written for a session without rows. If reading or writing fails, call
+/// [`abort`](PartitionedParquetSink::abort) to remove the file left without a footer.
+/// Writes are blocking.
+#[cfg(feature = "parquet")]
+pub struct PartitionedParquetSink {
+    path_template: String,
+    rows_per_file: usize,
+    max_bytes_per_file: Option<usize>,
+    props: Option<parquet::file::properties::WriterProperties>,
+    /// The file being written, along with the number of rows written into it so far and
+    /// the number of bytes flushed into it.
+    current: Option<(
+        parquet::arrow::ArrowWriter<CountingFile>,
+        usize,
+        Arc<AtomicUsize>,
+    )>,
+    paths: Vec<PathBuf>,
+}
+
+/// A file counting the bytes flushed into it, which the Parquet writer does not report.
+#[cfg(feature = "parquet")]
+struct CountingFile {
+    file: File,
+    bytes: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "parquet")]
+impl Write for CountingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.bytes.fetch_add(written, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl PartitionedParquetSink {
+    /// Write files named after `path_template` of at most `rows_per_file` rows, with the
+    /// writer `props` or the defaults of the `parquet` crate. Fails if `path_template`
+    /// has no `{}` or if `rows_per_file` is zero.
+    pub fn try_new(
+        path_template: &str,
+        rows_per_file: usize,
+        props: Option<parquet::file::properties::WriterProperties>,
+    ) -> Result<Self, Error> {
+        if !path_template.contains("{}") {
+            return Err(Error::InvalidOption {
+                name: "path_template",
+                reason: format!(
+                    "{:?} has no `{{}}` for the index of the files",
+                    path_template
+                ),
+            });
+        }
+        if rows_per_file == 0 {
+            return Err(Error::InvalidOption {
+                name: "rows_per_file",
+                reason: "files must hold at least one row".to_string(),
+            });
+        }
+        Ok(Self {
+            path_template: path_template.to_string(),
+            rows_per_file,
+            max_bytes_per_file: None,
+            props,
+            current: None,
+            paths: Vec::new(),
+        })
+    }
+
+    /// Also start a new file once the current one reaches about `bytes` bytes, encoded
+    /// and compressed. Files go over `bytes` by up to the size of a batch.
+    pub fn max_bytes_per_file(mut self, bytes: usize) -> Self {
+        self.max_bytes_per_file = Some(bytes);
+        self
+    }
+
+    /// The paths of the files written so far, the last one possibly still being written.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Close the file being written, writing its footer, and return the paths of all the
+    /// files written.
+    pub fn close(mut self) -> Result<Vec<PathBuf>, Error> {
+        self.roll()?;
+        Ok(self.paths)
+    }
+
+    /// Create the next file, for batches of the given `schema`.
+    fn open(&mut self, schema: arrow::datatypes::SchemaRef) -> Result<(), Error> {
+        let index = format!("{:04}", self.paths.len());
+        let path = PathBuf::from(self.path_template.replacen("{}", &index, 1));
+        let bytes = Arc::new(AtomicUsize::new(0));
+        let file = CountingFile {
+            file: File::create(&path)?,
+            bytes: bytes.clone(),
+        };
+        let writer = parquet::arrow::ArrowWriter::try_new(file, schema, self.props.clone())?;
+        self.paths.push(path);
+        self.current = Some((writer, 0, bytes));
+        Ok(())
+    }
+
+    /// Remove the file being written, if any, e.g. after a failed read: it has no footer
+    /// and cannot be read. The files completed before it are kept, and their paths are
+    /// returned.
+    pub fn abort(mut self) -> Result<Vec<PathBuf>, Error> {
+        if let Some((writer, _, _)) = self.current.take() {
+            drop(writer);
+            let path = self.paths.pop().expect("the file being written has a path");
+            std::fs::remove_file(path)?;
+        }
+        Ok(self.paths)
+    }
+
+    /// Close the file being written, if any.
+    fn roll(&mut self) -> Result<(), Error> {
+        if let Some((writer, _, _)) = self.current.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "parquet")]
+#[tonic::async_trait]
+impl RecordBatchSink for PartitionedParquetSink {
+    async fn write(&mut self, mut batch: RecordBatch) -> Result<(), Error> {
+        while batch.num_rows() > 0 {
+            if self.current.is_none() {
+                self.open(batch.schema())?;
+            }
+            let (writer, rows, bytes) = self.current.as_mut().expect("a file is open");
+
+            // Split the batch at the row limit of the file
+            let len = batch.num_rows().min(self.rows_per_file - *rows);
+            writer.write(&batch.slice(0, len))?;
+            *rows += len;
+            batch = batch.slice(len, batch.num_rows() - len);
+
+            let full = *rows == self.rows_per_file
+                || self.max_bytes_per_file.map_or(false, |max_bytes| {
+                    bytes.load(Ordering::Relaxed) + writer.in_progress_size() >= max_bytes
+                });
+            if full {
+                self.roll()?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn finish(self) -> Result<(), Error> {
+        self.close()?;
+        Ok(())
+    }
+
+    async fn abort(self) -> Result<(), Error> {
+        PartitionedParquetSink::abort(self)?;
+        Ok(())
+    }
+}